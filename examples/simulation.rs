@@ -1,3 +1,4 @@
+use advanced_pid::plant::{FirstOrder, Plant};
 use advanced_pid::{prelude::*, Pid, PidGain};
 
 fn main() {
@@ -9,6 +10,9 @@ fn main() {
     };
     let mut pid = Pid::new(gain.into());
 
+    // First order lag with a time constant of 300 ms
+    let mut plant: FirstOrder = FirstOrder::new(1.0, 0.3);
+
     let target = 1.0;
     let mut actual = 0.0;
     let dt = 0.1;
@@ -17,7 +21,7 @@ fn main() {
         let output = pid.update(target, actual, dt);
 
         // Simulate the system response
-        actual += (output - actual) / 4.0;
+        actual = plant.step(output, dt);
         println!("{:5.2}", actual);
 
         // Sleep 100ms
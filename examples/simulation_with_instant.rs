@@ -1,34 +1,30 @@
+use advanced_pid::clock::{Clocked, StdClock};
+use advanced_pid::plant::{FirstOrder, Plant};
 use advanced_pid::{prelude::*, PidConfig, VelPid};
 
 use std::time::{Duration, Instant};
 
 fn main() {
-    let mut pid = VelPid::default();
     let config = PidConfig::new(0.8, 0.3, 0.2).with_limits(-1.2, 1.2);
-    pid.reset_config(config);
+    let mut pid = Clocked::new(VelPid::new(config), StdClock);
+
+    // First order lag with a time constant of 7 s
+    let mut plant: FirstOrder = FirstOrder::new(1.0, 7.0);
 
     let target = 1.0;
     let mut actual = 0.0;
 
+    // The first update only starts the clock.
     let mut pre = Instant::now();
+    pid.update_at(target, actual, pre);
     loop {
         let now = Instant::now();
-        let duration = pre.elapsed();
+        let duration = now - pre;
         if duration > Duration::from_secs(1) {
-            let sec = as_secs(duration);
-            let output = pid.update(target, actual, sec);
-            actual += (output - actual) / 8.0;
+            let output = pid.update_at(target, actual, now);
+            actual = plant.step(output, duration.as_secs_f32());
             println!("{:5.2}\t{:5.2}\t{:?}", actual, output, duration);
             pre = now;
         }
     }
 }
-
-#[cfg(not(feature = "f64"))]
-fn as_secs(duration: Duration) -> f32 {
-    duration.as_secs_f32()
-}
-#[cfg(feature = "f64")]
-fn as_secs(duration: Duration) -> f64 {
-    duration.as_secs_f64()
-}
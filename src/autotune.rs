@@ -0,0 +1,420 @@
+//! The `autotune` module provides a relay-feedback autotuner.
+//!
+//! [`RelayAutotuner`] implements the Åström–Hägglund relay experiment.
+//! It is stepped in the control loop in place of a controller and switches its output between two levels around a bias,
+//! which makes most plants oscillate at their ultimate period.
+//! Once the oscillation is sustained, the ultimate gain and period are measured
+//! and a [`Config`] can be derived from them with one of the [`tuning`](crate::tuning) rules.
+//!
+//! The experiment fails after a timeout, or as soon as the actual value leaves the allowed range,
+//! in which case the autotuner falls back to the bias output.
+//!
+//! # Examples
+//!
+//! ```rust
+//! use advanced_pid::autotune::{RelayAutotuner, Status};
+//! use advanced_pid::tuning::{Structure, UltimateRule};
+//!
+//! let mut autotuner = RelayAutotuner::new(1.0, 0.5)
+//!     .with_bias(0.5)
+//!     .with_hysteresis(0.01)
+//!     .with_timeout(60.0)
+//!     .with_actual_limits(-1.0, 3.0);
+//!
+//! let actual = 0.0;
+//! let dt = 0.01;
+//! let output = autotuner.update(actual, dt);
+//!
+//! if let Status::Done(ultimate) = autotuner.status() {
+//!     let config = ultimate.config(UltimateRule::TyreusLuyben, Structure::Pi);
+//!     println!("{:?}", config.gain);
+//! }
+//! ```
+use super::config::{Config, ConfigError};
+use super::float::sqrt;
+use super::tuning::{Structure, UltimateRule};
+use super::Float;
+
+pub use super::tuning::Ultimate;
+
+/// `AutotuneError` is the reason why the relay experiment failed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AutotuneError {
+    /// No sustained oscillation was detected before the timeout.
+    Timeout,
+    /// The actual value left the allowed range.
+    OutOfLimits,
+}
+
+/// `Status` is the progress of the relay experiment.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Status<F = f32> {
+    /// The experiment is in progress.
+    Running,
+    /// A sustained oscillation was measured.
+    Done(Ultimate<F>),
+    /// The experiment was aborted.
+    Failed(AutotuneError),
+}
+
+/// `RelayAutotuner` measures the ultimate gain and period of a plant with relay feedback.
+///
+/// It assumes a direct-acting plant, whose actual value rises with the output.
+#[derive(Debug, Clone)]
+pub struct RelayAutotuner<F = f32> {
+    set_point: F,
+    amplitude: F,
+    bias: F,
+    hysteresis: F,
+    timeout: F,
+    actual_min: F,
+    actual_max: F,
+    cycles: usize,
+    tolerance: F,
+    status: Status<F>,
+    high: bool,
+    time: F,
+    cycle_start: F,
+    cycle_min: F,
+    cycle_max: F,
+    pre_period: F,
+    pre_amplitude: F,
+    consistent_cycles: usize,
+    period_sum: F,
+    amplitude_sum: F,
+}
+
+impl<F: Float> RelayAutotuner<F> {
+    /// Creates a new `RelayAutotuner` that oscillates around the specified set point
+    /// by switching its output by the specified amplitude above and below the bias, which is zero by default.
+    pub fn new(set_point: F, amplitude: F) -> Self {
+        Self {
+            set_point,
+            amplitude,
+            bias: F::ZERO,
+            hysteresis: F::ZERO,
+            timeout: F::INFINITY,
+            actual_min: F::NEG_INFINITY,
+            actual_max: F::INFINITY,
+            cycles: 3,
+            tolerance: F::from_f64(0.05),
+            status: Status::Running,
+            high: true,
+            time: F::ZERO,
+            cycle_start: F::NAN,
+            cycle_min: F::INFINITY,
+            cycle_max: F::NEG_INFINITY,
+            pre_period: F::NAN,
+            pre_amplitude: F::NAN,
+            consistent_cycles: 0,
+            period_sum: F::ZERO,
+            amplitude_sum: F::ZERO,
+        }
+    }
+
+    /// Returns a new `RelayAutotuner` with the specified bias, the output around which the relay switches.
+    /// It is also the output once the experiment is over.
+    pub fn with_bias(self, bias: F) -> Self {
+        Self { bias, ..self }
+    }
+
+    /// Returns a new `RelayAutotuner` with the specified hysteresis.
+    /// The relay only switches once the error exceeds it, which keeps measurement noise from chattering the relay.
+    pub fn with_hysteresis(self, hysteresis: F) -> Self {
+        Self { hysteresis, ..self }
+    }
+
+    /// Returns a new `RelayAutotuner` that fails if no sustained oscillation is detected within the specified time, in seconds.
+    pub fn with_timeout(self, timeout: F) -> Self {
+        Self { timeout, ..self }
+    }
+
+    /// Returns a new `RelayAutotuner` that fails as soon as the actual value leaves the specified range.
+    pub fn with_actual_limits(self, actual_min: F, actual_max: F) -> Self {
+        Self {
+            actual_min,
+            actual_max,
+            ..self
+        }
+    }
+
+    /// Returns a new `RelayAutotuner` that requires the specified number of consecutive consistent cycles,
+    /// whose period and amplitude are within the specified relative tolerance of the previous cycle.
+    /// By default, 3 cycles within 5% are required.
+    /// At least one cycle is always required, so zero cycles are taken as one.
+    pub fn with_cycles(self, cycles: usize, tolerance: F) -> Self {
+        Self {
+            cycles: cycles.max(1),
+            tolerance,
+            ..self
+        }
+    }
+
+    /// Returns a new `RelayAutotuner` that requires the specified number of consecutive consistent cycles,
+    /// after validating them.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`ConfigError::InvalidParameter`] if `cycles` is zero, or if the tolerance is negative or NaN.
+    pub fn try_with_cycles(self, cycles: usize, tolerance: F) -> Result<Self, ConfigError> {
+        if cycles == 0 || tolerance.is_nan() || tolerance < F::ZERO {
+            return Err(ConfigError::InvalidParameter);
+        }
+        Ok(self.with_cycles(cycles, tolerance))
+    }
+
+    /// Updates the autotuner with the specified actual value and time delta.
+    /// Returns the relay output, or the bias once the experiment is over.
+    pub fn update(&mut self, actual: F, dt: F) -> F {
+        if self.status != Status::Running {
+            return self.bias;
+        }
+        self.time += dt;
+        if !(self.actual_min..=self.actual_max).contains(&actual) {
+            self.status = Status::Failed(AutotuneError::OutOfLimits);
+            return self.bias;
+        }
+        if self.time > self.timeout {
+            self.status = Status::Failed(AutotuneError::Timeout);
+            return self.bias;
+        }
+
+        self.cycle_min = self.cycle_min.min(actual);
+        self.cycle_max = self.cycle_max.max(actual);
+        let error = self.set_point - actual;
+        if self.high && error < -self.hysteresis {
+            self.high = false;
+        } else if !self.high && error > self.hysteresis {
+            // A cycle spans from one switch up to the next.
+            self.high = true;
+            self.end_cycle();
+        }
+
+        if self.high {
+            self.bias + self.amplitude
+        } else {
+            self.bias - self.amplitude
+        }
+    }
+
+    /// Measures the cycle that has just ended, and finishes the experiment once enough cycles are consistent.
+    fn end_cycle(&mut self) {
+        let period = self.time - self.cycle_start;
+        let amplitude = (self.cycle_max - self.cycle_min) / F::from_f64(2.0);
+        self.cycle_start = self.time;
+        self.cycle_min = F::INFINITY;
+        self.cycle_max = F::NEG_INFINITY;
+        if period.is_nan() {
+            // The first switch up only starts the first cycle.
+            return;
+        }
+
+        let close = |value: F, previous: F| (value - previous).abs() <= self.tolerance * value;
+        if close(period, self.pre_period) && close(amplitude, self.pre_amplitude) {
+            self.consistent_cycles += 1;
+            self.period_sum += period;
+            self.amplitude_sum += amplitude;
+        } else {
+            // The first cycle after a transient only serves as a reference.
+            self.consistent_cycles = 0;
+            self.period_sum = F::ZERO;
+            self.amplitude_sum = F::ZERO;
+        }
+        self.pre_period = period;
+        self.pre_amplitude = amplitude;
+
+        if self.consistent_cycles >= self.cycles {
+            let count = F::from_f64(self.consistent_cycles as f64);
+            let period = self.period_sum / count;
+            let amplitude = self.amplitude_sum / count;
+            // Describing function of a relay with hysteresis: N(a) = 4d / (π √(a² − ε²)).
+            let squared = amplitude * amplitude - self.hysteresis * self.hysteresis;
+            let effective = if squared > F::ZERO {
+                sqrt(squared)
+            } else {
+                amplitude
+            };
+            let gain = F::from_f64(4.0 / core::f64::consts::PI) * self.amplitude / effective;
+            self.status = Status::Done(Ultimate { gain, period });
+        }
+    }
+
+    /// Returns the progress of the experiment.
+    pub fn status(&self) -> Status<F> {
+        self.status
+    }
+
+    /// Returns the measured ultimate gain and period, if the experiment is done.
+    pub fn ultimate(&self) -> Option<Ultimate<F>> {
+        match self.status {
+            Status::Done(ultimate) => Some(ultimate),
+            _ => None,
+        }
+    }
+
+    /// Returns a `Config` derived with the specified tuning rule and controller structure, if the experiment is done.
+    pub fn config(&self, rule: UltimateRule, structure: Structure) -> Option<Config<F>> {
+        self.ultimate()
+            .map(|ultimate| ultimate.config(rule, structure))
+    }
+
+    /// Returns the time elapsed since the start of the experiment, in seconds.
+    pub fn elapsed(&self) -> F {
+        self.time
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Third order lag `1 / (s + 1)³`, whose ultimate gain is 8 and ultimate period is 2π/√3.
+    struct ThirdOrder([f64; 3]);
+
+    impl ThirdOrder {
+        fn step(&mut self, input: f64, dt: f64) -> f64 {
+            let x = &mut self.0;
+            x[0] += (input - x[0]) * dt;
+            x[1] += (x[0] - x[1]) * dt;
+            x[2] += (x[1] - x[2]) * dt;
+            x[2]
+        }
+    }
+
+    /// First order lag with dead time `e^(-sL) / (τs + 1)`.
+    struct DeadTime {
+        delay: [f64; 50],
+        index: usize,
+        state: f64,
+    }
+
+    impl DeadTime {
+        fn step(&mut self, input: f64, dt: f64) -> f64 {
+            let delayed = core::mem::replace(&mut self.delay[self.index], input);
+            self.index = (self.index + 1) % self.delay.len();
+            self.state += (delayed - self.state) * dt;
+            self.state
+        }
+    }
+
+    fn run(autotuner: &mut RelayAutotuner<f64>, mut plant: impl FnMut(f64) -> f64, dt: f64) {
+        let mut actual = 0.0;
+        for _ in 0..100_000 {
+            let output = autotuner.update(actual, dt);
+            if autotuner.status() != Status::Running {
+                return;
+            }
+            actual = plant(output);
+        }
+    }
+
+    #[test]
+    fn test_relay_autotuner_third_order() {
+        let mut autotuner = RelayAutotuner::new(0.0, 1.0).with_timeout(100.0);
+        let mut plant = ThirdOrder([0.0; 3]);
+        run(&mut autotuner, |u| plant.step(u, 0.001), 0.001);
+
+        let ultimate = autotuner.ultimate().expect("the experiment must be done");
+        let expected_period = 3.6276;
+        // The describing function analysis underestimates the ultimate gain by a few percent.
+        assert!((ultimate.gain - 8.0).abs() < 0.8, "gain: {}", ultimate.gain);
+        assert!(
+            (ultimate.period - expected_period).abs() < 0.05 * expected_period,
+            "period: {}",
+            ultimate.period
+        );
+        let config = autotuner
+            .config(UltimateRule::ZieglerNichols, Structure::Pi)
+            .unwrap();
+        assert_eq!(config.gain.kp, 0.45 * ultimate.gain);
+        assert_eq!(config.gain.kd, 0.0);
+    }
+
+    #[test]
+    fn test_relay_autotuner_dead_time_with_hysteresis() {
+        let mut autotuner = RelayAutotuner::new(1.0, 0.5)
+            .with_bias(1.0)
+            .with_hysteresis(0.05);
+        let mut plant = DeadTime {
+            delay: [0.0; 50],
+            index: 0,
+            state: 0.0,
+        };
+        let dt = 0.01;
+        run(&mut autotuner, |u| plant.step(u, dt), dt);
+
+        // A relay with hysteresis d = 0.5, ε = 0.05 around a dead time L = 0.5 and a time constant of 1 oscillates with
+        // an amplitude a = d − (d − ε) e^(−L) and a period of 2 (L + ln((d + a) / (d − ε))).
+        let (period, gain) = (1.9595, 2.8743);
+        let ultimate = autotuner.ultimate().expect("the experiment must be done");
+        assert!(
+            (ultimate.period - period).abs() < 0.05,
+            "period: {}",
+            ultimate.period
+        );
+        assert!(
+            (ultimate.gain - gain).abs() < 0.05 * gain,
+            "gain: {}",
+            ultimate.gain
+        );
+        assert_eq!(autotuner.update(1.0, dt), 1.0);
+    }
+
+    #[test]
+    fn test_relay_autotuner_cycles() {
+        assert_eq!(
+            RelayAutotuner::new(0.0, 1.0)
+                .try_with_cycles(0, 0.05)
+                .unwrap_err(),
+            ConfigError::InvalidParameter
+        );
+        assert_eq!(
+            RelayAutotuner::new(0.0, 1.0)
+                .try_with_cycles(2, f64::NAN)
+                .unwrap_err(),
+            ConfigError::InvalidParameter
+        );
+
+        // Zero cycles are taken as one, so the experiment still measures a cycle.
+        let mut autotuner = RelayAutotuner::new(0.0, 1.0)
+            .with_cycles(0, 0.05)
+            .with_timeout(100.0);
+        let mut plant = ThirdOrder([0.0; 3]);
+        run(&mut autotuner, |u| plant.step(u, 0.001), 0.001);
+        match autotuner.status() {
+            Status::Done(ultimate) => {
+                assert!(ultimate.gain.is_finite() && ultimate.period.is_finite())
+            }
+            status => panic!("{:?}", status),
+        }
+    }
+
+    #[test]
+    fn test_relay_autotuner_timeout() {
+        // The plant never reaches the set point, so the relay never switches.
+        let mut autotuner = RelayAutotuner::new(10.0, 1.0).with_timeout(5.0);
+        let mut plant = ThirdOrder([0.0; 3]);
+        run(&mut autotuner, |u| plant.step(u, 0.01), 0.01);
+
+        assert_eq!(autotuner.status(), Status::Failed(AutotuneError::Timeout));
+        assert!(autotuner.elapsed() > 5.0);
+        assert!(autotuner
+            .config(UltimateRule::ZieglerNichols, Structure::Pid)
+            .is_none());
+    }
+
+    #[test]
+    fn test_relay_autotuner_out_of_limits() {
+        let mut autotuner = RelayAutotuner::new(0.0, 2.0)
+            .with_bias(1.0)
+            .with_actual_limits(-0.2, 0.2);
+        let mut plant = ThirdOrder([0.0; 3]);
+        run(&mut autotuner, |u| plant.step(u, 0.01), 0.01);
+
+        assert_eq!(
+            autotuner.status(),
+            Status::Failed(AutotuneError::OutOfLimits)
+        );
+        assert_eq!(autotuner.update(0.0, 0.01), 1.0);
+    }
+}
@@ -0,0 +1,463 @@
+//! The `cascade` module provides a combinator for cascade control.
+//!
+//! [`Cascade`] feeds the output of an outer controller to an inner loop as its set point.
+//! The outer controller can be any [`PidController`] that implements [`Integrator`] and [`ManualMode`],
+//! and the inner loop any [`Loop`]: a controller, or another `Cascade` for three or more nested loops.
+//! The outer loop can run at a lower rate than the inner loop.
+//!
+//! While the inner loop's output is saturated, by its output limits, its rate limits, or the integrator limits of a `VelPid`,
+//! the outer controller's integral is held whenever it would drive the inner set point further into saturation,
+//! so it does not wind up (conditional integration).
+//!
+//! # Examples
+//!
+//! ```rust
+//! use advanced_pid::{cascade::Cascade, prelude::*, Pid, PidConfig};
+//!
+//! let position = Pid::new(PidConfig::new(2.0, 0.1, 0.0));
+//! let velocity = Pid::new(PidConfig::new(0.5, 1.0, 0.0).with_limits(-1.0, 1.0));
+//! // Update the position loop every 10 ms, and the velocity loop on every call.
+//! let mut cascade = Cascade::new(position, velocity).with_outer_period(0.01);
+//!
+//! let target = 1.0;
+//! let (position, velocity) = (0.0, 0.0);
+//! let dt = 0.001;
+//!
+//! println!("{}", cascade.update(target, position, velocity, dt));
+//! ```
+//!
+//! A cascade is itself a [`Loop`], so it can be the inner loop of another cascade.
+//! Its actual values are then a pair of the outer and inner actual values.
+//!
+//! ```rust
+//! use advanced_pid::{cascade::Cascade, prelude::*, Pid, PidConfig};
+//!
+//! let position = Pid::new(PidConfig::new(2.0, 0.1, 0.0));
+//! let velocity = Pid::new(PidConfig::new(0.5, 1.0, 0.0));
+//! let current = Pid::new(PidConfig::new(1.0, 10.0, 0.0).with_limits(-12.0, 12.0));
+//! let mut cascade = Cascade::new(position, Cascade::new(velocity, current));
+//!
+//! let (position, velocity, current) = (0.0, 0.0, 0.0);
+//! println!("{}", cascade.update(1.0, position, (velocity, current), 0.001));
+//! ```
+use super::config::ConfigError;
+use super::Configurable;
+use super::Float;
+use super::Integrator;
+use super::ManualMode;
+use super::Mode;
+use super::PidController;
+
+/// `Loop` is a control loop that a [`Cascade`] can drive: a controller of this crate, or a `Cascade` itself.
+///
+/// Other [`Configurable`] controllers can implement it with [`controller_saturation()`] and [`Config::validate()`](crate::config::Config::validate()).
+pub trait Loop<F: Float = f32> {
+    /// The actual values of the loop: one value for a controller, and a pair for a cascade.
+    type Actual: Copy;
+
+    /// Updates the loop with the specified set point, actual values, and time delta.
+    /// Returns the loop output.
+    fn update_loop(&mut self, set_point: F, actual: Self::Actual, dt: F) -> F;
+
+    /// Returns the direction in which the loop's last output, `output`, is saturated:
+    /// one at the upper limits, minus one at the lower limits, and zero otherwise.
+    fn saturation(&self, output: F) -> F;
+
+    /// Checks the configuration of every controller in the loop with [`Config::validate()`](crate::config::Config::validate()).
+    fn validate(&self) -> Result<(), ConfigError>;
+}
+
+macro_rules! impl_loop {
+    ($($controller:ident),*) => {$(
+        impl<F: Float> Loop<F> for super::$controller<F> {
+            type Actual = F;
+
+            fn update_loop(&mut self, set_point: F, actual: F, dt: F) -> F {
+                self.update(set_point, actual, dt)
+            }
+
+            fn saturation(&self, output: F) -> F {
+                controller_saturation(self, output)
+            }
+
+            fn validate(&self) -> Result<(), ConfigError> {
+                self.config().validate()
+            }
+        }
+    )*};
+}
+
+impl_loop!(Pid, PiD, Ipd, TwoDofPid, VelPid);
+
+/// Returns the direction in which a controller's last output, `output`, is saturated.
+///
+/// The [terms](PidController::last_terms()) account for the rate limits;
+/// a controller without terms is compared with its output limits.
+/// Implementations of [`Loop`] for other controllers can use it as their [`Loop::saturation()`].
+pub fn controller_saturation<F: Float, C: Configurable<F>>(controller: &C, output: F) -> F {
+    match controller.last_terms() {
+        Some(terms) if !terms.saturated => F::ZERO,
+        Some(terms) => direction(terms.unclamped - terms.output),
+        None if output >= controller.config().max => F::ONE,
+        None if output <= controller.config().min => -F::ONE,
+        None => F::ZERO,
+    }
+}
+
+fn direction<F: Float>(value: F) -> F {
+    if value > F::ZERO {
+        F::ONE
+    } else if value < F::ZERO {
+        -F::ONE
+    } else {
+        F::ZERO
+    }
+}
+
+/// `Cascade` is a combinator that drives an inner loop with the output of an outer controller.
+#[derive(Debug, Clone)]
+pub struct Cascade<Outer, Inner, F = f32> {
+    outer: Outer,
+    inner: Inner,
+    outer_period: F,
+    elapsed: F,
+    inner_set_point: F,
+    inner_saturation: F,
+}
+
+impl<Outer, Inner, F> Cascade<Outer, Inner, F>
+where
+    Outer: Integrator<F> + ManualMode<F>,
+    Inner: Loop<F>,
+    F: Float,
+{
+    /// Creates a new `Cascade` with the specified outer controller and inner loop.
+    /// Both loops are updated on every call to [`Self::update()`].
+    pub fn new(outer: Outer, inner: Inner) -> Self {
+        Self {
+            outer,
+            inner,
+            outer_period: F::ZERO,
+            elapsed: F::ZERO,
+            inner_set_point: F::NAN,
+            inner_saturation: F::ZERO,
+        }
+    }
+
+    /// Creates a new `Cascade` after validating the configurations of the outer controller and of the controllers of the inner loop.
+    ///
+    /// # Errors
+    ///
+    /// Returns the first [`ConfigError`] found by [`Config::validate()`](crate::config::Config::validate()).
+    pub fn try_new(outer: Outer, inner: Inner) -> Result<Self, ConfigError> {
+        let cascade = Self::new(outer, inner);
+        Loop::validate(&cascade)?;
+        Ok(cascade)
+    }
+
+    /// Returns a new `Cascade` whose outer loop is updated once the specified period has elapsed.
+    /// The outer controller is then updated with the elapsed time as its time delta.
+    pub fn with_outer_period(self, outer_period: F) -> Self {
+        Self {
+            outer_period,
+            ..self
+        }
+    }
+
+    /// Updates the cascade with the specified set point, actual values of the outer and inner loops, and time delta.
+    /// Returns the output of the inner loop.
+    pub fn update(
+        &mut self,
+        set_point: F,
+        outer_actual: F,
+        inner_actual: Inner::Actual,
+        dt: F,
+    ) -> F {
+        self.elapsed += dt;
+        if self.inner_set_point.is_nan() || self.elapsed >= self.outer_period {
+            let outer_dt = self.elapsed;
+            self.elapsed = F::ZERO;
+            self.inner_set_point = self.update_outer(set_point, outer_actual, outer_dt);
+        }
+        let output = self
+            .inner
+            .update_loop(self.inner_set_point, inner_actual, dt);
+        self.inner_saturation = self.inner.saturation(output);
+        output
+    }
+
+    /// Updates the outer controller.
+    /// While the inner loop is saturated, the integral is held if it would move further in the same direction:
+    /// the outer controller is updated with its integrator limits narrowed to the current integral,
+    /// so that its output, rate limits and terms follow from the held integral.
+    fn update_outer(&mut self, set_point: F, actual: F, dt: F) -> F {
+        let held = self.outer.integral();
+        if self.inner_saturation == F::ZERO || self.outer.mode() != Mode::Automatic || held.is_nan()
+        {
+            return self.outer.update(set_point, actual, dt);
+        }
+        let config = self.outer.config().clone();
+        let holding = if self.inner_saturation > F::ZERO {
+            config
+                .clone()
+                .with_integral_limits(config.i_min, held.min(config.i_max))
+        } else {
+            config
+                .clone()
+                .with_integral_limits(held.max(config.i_min), config.i_max)
+        };
+        self.outer.set_config(holding);
+        let output = self.outer.update(set_point, actual, dt);
+        self.outer.set_config(config);
+        output
+    }
+
+    /// Returns the set point currently applied to the inner loop, or NaN before the first update.
+    pub fn inner_set_point(&self) -> F {
+        self.inner_set_point
+    }
+
+    /// Returns `true` if the inner loop's last output was at its limits or rate limits.
+    pub fn is_inner_saturated(&self) -> bool {
+        self.inner_saturation != F::ZERO
+    }
+
+    /// Returns a reference to the outer controller.
+    pub fn outer(&self) -> &Outer {
+        &self.outer
+    }
+
+    /// Returns a mutable reference to the outer controller.
+    pub fn outer_mut(&mut self) -> &mut Outer {
+        &mut self.outer
+    }
+
+    /// Returns a reference to the inner loop.
+    pub fn inner(&self) -> &Inner {
+        &self.inner
+    }
+
+    /// Returns a mutable reference to the inner loop.
+    pub fn inner_mut(&mut self) -> &mut Inner {
+        &mut self.inner
+    }
+
+    /// Consumes the cascade and returns the outer controller and the inner loop.
+    pub fn into_inner(self) -> (Outer, Inner) {
+        (self.outer, self.inner)
+    }
+}
+
+impl<Outer, Inner, F> Loop<F> for Cascade<Outer, Inner, F>
+where
+    Outer: Integrator<F> + ManualMode<F>,
+    Inner: Loop<F>,
+    F: Float,
+{
+    type Actual = (F, Inner::Actual);
+
+    fn update_loop(&mut self, set_point: F, actual: Self::Actual, dt: F) -> F {
+        self.update(set_point, actual.0, actual.1, dt)
+    }
+
+    /// Returns the saturation of the inner loop, or else of the outer controller's output.
+    fn saturation(&self, _output: F) -> F {
+        if self.inner_saturation != F::ZERO {
+            self.inner_saturation
+        } else {
+            controller_saturation(&self.outer, self.inner_set_point)
+        }
+    }
+
+    fn validate(&self) -> Result<(), ConfigError> {
+        self.outer.config().validate()?;
+        self.inner.validate()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Pid, PidConfig};
+
+    /// Simulates a position loop around a saturating velocity loop and returns the peak position.
+    fn peak_position(mut step: impl FnMut(f32, f32, f32) -> f32) -> f32 {
+        let dt = 0.01;
+        let (mut position, mut velocity): (f32, f32) = (0.0, 0.0);
+        let mut peak: f32 = 0.0;
+        for _ in 0..3000 {
+            let output = step(position, velocity, dt);
+            velocity += (output - velocity) * dt * 10.0;
+            position += velocity * dt;
+            peak = peak.max(position);
+        }
+        peak
+    }
+
+    fn outer_config() -> PidConfig {
+        PidConfig::new(2.0, 1.0, 0.0)
+    }
+
+    fn inner_config() -> PidConfig {
+        PidConfig::new(1.0, 5.0, 0.0)
+            .with_limits(-0.5, 0.5)
+            .with_anti_windup(crate::config::AntiWindup::Conditional)
+    }
+
+    #[test]
+    fn test_cascade_prevents_outer_windup() {
+        let mut outer = Pid::new(outer_config());
+        let mut inner = Pid::new(inner_config());
+        let by_hand = peak_position(|position, velocity, dt| {
+            let velocity_set_point = outer.update(2.0, position, dt);
+            inner.update(velocity_set_point, velocity, dt)
+        });
+
+        let mut cascade = Cascade::new(Pid::new(outer_config()), Pid::new(inner_config()));
+        let cascaded =
+            peak_position(|position, velocity, dt| cascade.update(2.0, position, velocity, dt));
+
+        assert!(by_hand > 2.5, "peak wired by hand: {}", by_hand);
+        assert!(cascaded < 2.1, "peak with cascade: {}", cascaded);
+    }
+
+    #[test]
+    fn test_cascade_try_new() {
+        let cascade = Cascade::try_new(Pid::new(outer_config()), Pid::new(inner_config()));
+        assert!(cascade.is_ok());
+
+        let inverted = PidConfig::new(1.0, 1.0, 0.0).with_limits(1.0, -1.0);
+        let nested = Cascade::new(Pid::new(inner_config()), Pid::new(inverted));
+        assert_eq!(
+            Cascade::try_new(Pid::new(outer_config()), nested).unwrap_err(),
+            ConfigError::InvertedLimits
+        );
+        let negative = PidConfig::new(1.0, -1.0, 0.0);
+        assert_eq!(
+            Cascade::try_new(Pid::new(negative), Pid::new(inner_config())).unwrap_err(),
+            ConfigError::NegativeGain
+        );
+    }
+
+    #[test]
+    fn test_cascade_outer_period() {
+        let outer = Pid::new(PidConfig::new(0.0, 1.0, 0.0));
+        let inner = Pid::new(PidConfig::new(1.0, 0.0, 0.0));
+        let mut cascade = Cascade::new(outer, inner).with_outer_period(1.0);
+
+        assert_eq!(cascade.update(1.0, 0.0, 0.0, 0.25), 0.25);
+        assert_eq!(cascade.update(1.0, 0.0, 0.0, 0.25), 0.25);
+        assert_eq!(cascade.update(1.0, 0.0, 0.0, 0.25), 0.25);
+        assert_eq!(cascade.update(1.0, 0.0, 0.0, 0.25), 0.25);
+        assert_eq!(cascade.update(1.0, 0.0, 0.0, 0.25), 1.25);
+        assert_eq!(cascade.inner_set_point(), 1.25);
+    }
+
+    #[test]
+    fn test_cascade_inner_saturation() {
+        let outer = Pid::new(PidConfig::new(0.0, 1.0, 0.0));
+        let inner = PidConfig::new(0.0, 1.0, 0.0)
+            .with_limits(-1.0, 1.0)
+            .with_anti_windup(crate::config::AntiWindup::Conditional);
+        let inner = Pid::new(inner);
+        let mut cascade = Cascade::new(outer, inner);
+
+        assert_eq!(cascade.update(2.0, 0.0, 0.0, 1.0), 1.0);
+        assert!(cascade.is_inner_saturated());
+        // The outer integral is held instead of winding up.
+        assert_eq!(cascade.update(2.0, 0.0, 0.5, 1.0), 1.0);
+        assert_eq!(cascade.inner_set_point(), 2.0);
+        assert_eq!(cascade.outer().integral(), 2.0);
+        assert_eq!(cascade.update(2.0, 0.0, 0.5, 1.0), 1.0);
+        assert_eq!(cascade.outer().integral(), 2.0);
+        // The outer loop integrates again as soon as it asks for less.
+        cascade.update(-1.0, 0.0, 0.5, 1.0);
+        assert_eq!(cascade.inner_set_point(), 1.0);
+        assert_eq!(cascade.outer().integral(), 1.0);
+    }
+
+    #[test]
+    fn test_cascade_outer_saturation() {
+        let outer = PidConfig::new(0.0, 1.0, 0.0).with_limits(-1.0, 1.0);
+        let mut outer = Pid::new(outer);
+        outer.set_integral(2.0);
+        let inner = Pid::new(PidConfig::new(1.0, 0.0, 0.0).with_limits(-0.5, 0.5));
+        let mut cascade = Cascade::new(outer, inner);
+
+        assert_eq!(cascade.update(1.0, 0.0, 0.0, 1.0), 0.5);
+        assert_eq!(cascade.outer().integral(), 3.0);
+        // The inner set point stays at the outer limit while the outer integral is held.
+        for _ in 0..3 {
+            assert_eq!(cascade.update(1.0, 0.0, 0.0, 1.0), 0.5);
+            assert_eq!(cascade.inner_set_point(), 1.0);
+            assert_eq!(cascade.outer().integral(), 3.0);
+            assert_eq!(cascade.outer().last_terms().unwrap().output, 1.0);
+        }
+        assert_eq!(
+            cascade.outer().integral_limits(),
+            (f32::NEG_INFINITY, f32::INFINITY)
+        );
+    }
+
+    #[test]
+    fn test_cascade_inner_rate_limits() {
+        let outer = Pid::new(PidConfig::new(0.0, 1.0, 0.0));
+        let inner = Pid::new(PidConfig::new(1.0, 0.0, 0.0).with_rate_limits(1.0, 1.0));
+        let mut cascade = Cascade::new(outer, inner);
+
+        assert_eq!(cascade.update(2.0, 0.0, 0.0, 1.0), 1.0);
+        assert!(cascade.is_inner_saturated());
+        // A rate-limited inner output holds the outer integral as well.
+        assert_eq!(cascade.update(2.0, 0.0, 0.0, 1.0), 2.0);
+        assert_eq!(cascade.outer().integral(), 2.0);
+    }
+
+    #[test]
+    fn test_cascade_three_loops() {
+        let dt = 0.001;
+        // Simulates a motor whose current follows the saturating voltage, and returns the peak and final positions.
+        let run = |step: &mut dyn FnMut(f32, f32, f32) -> f32| {
+            let (mut position, mut velocity, mut current): (f32, f32, f32) = (0.0, 0.0, 0.0);
+            let mut peak: f32 = 0.0;
+            for _ in 0..20000 {
+                let voltage = step(position, velocity, current);
+                current += (voltage - current) * dt * 50.0;
+                velocity += current * dt * 5.0;
+                position += velocity * dt;
+                peak = peak.max(position);
+            }
+            (peak, position)
+        };
+        let position_loop = || Pid::new(PidConfig::new(2.0, 0.5, 0.0));
+        let velocity_loop = || Pid::new(PidConfig::new(2.0, 2.0, 0.0));
+        let current_loop = || {
+            Pid::new(
+                PidConfig::new(1.0, 20.0, 0.0)
+                    .with_limits(-1.0, 1.0)
+                    .with_anti_windup(crate::config::AntiWindup::Conditional),
+            )
+        };
+        let (mut outer, mut middle, mut inner) = (position_loop(), velocity_loop(), current_loop());
+        let by_hand = run(&mut |position, velocity, current| {
+            let velocity_set_point = outer.update(2.0, position, dt);
+            let current_set_point = middle.update(velocity_set_point, velocity, dt);
+            inner.update(current_set_point, current, dt)
+        });
+
+        let mut cascade = Cascade::new(
+            position_loop(),
+            Cascade::new(velocity_loop(), current_loop()),
+        );
+        let cascaded = run(&mut |position, velocity, current| {
+            cascade.update(2.0, position, (velocity, current), dt)
+        });
+
+        assert!(by_hand.0 > 2.5, "peak wired by hand: {}", by_hand.0);
+        assert!(cascaded.0 < 2.15, "peak with cascade: {}", cascaded.0);
+        assert!(
+            (cascaded.1 - 2.0).abs() < 0.01,
+            "final position: {}",
+            cascaded.1
+        );
+    }
+}
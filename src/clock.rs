@@ -0,0 +1,286 @@
+//! The `clock` module provides timestamp-driven updates.
+//!
+//! A [`Clock`] turns two timestamps into the seconds elapsed between them.
+//! [`StdClock`] works with [`std::time::Instant`], and [`TickClock`] with the wrapping tick counters of microcontrollers.
+//!
+//! [`Clocked`] wraps any [`PidController`] and computes the time delta from the timestamps passed to [`Clocked::update_at()`],
+//! so callers do not have to keep the time of the previous update.
+//!
+//! # Examples
+//!
+//! ```rust
+//! use advanced_pid::{clock::{Clocked, TickClock}, prelude::*, Pid, PidConfig};
+//!
+//! // A 16-bit counter incremented every millisecond.
+//! let clock = TickClock::new(0.001).with_bits(16);
+//! let mut pid = Clocked::new(Pid::new(PidConfig::new(1.0, 0.3, 0.1)), clock).with_first_dt(0.01);
+//!
+//! let target = 1.0;
+//! let actual = 0.0;
+//!
+//! println!("{}", pid.update_at(target, actual, 65530));
+//! // The counter wrapped around: 10 ticks have elapsed.
+//! println!("{}", pid.update_at(target, actual, 4));
+//! ```
+use super::Float;
+use super::PidController;
+
+/// `Clock` measures the time between two timestamps.
+pub trait Clock<F> {
+    /// The timestamp type, such as an instant or a tick count.
+    type Instant: Copy;
+
+    /// Returns the seconds elapsed from `earlier` to `later`,
+    /// or `None` if `later` is not after `earlier`.
+    fn elapsed(&self, earlier: Self::Instant, later: Self::Instant) -> Option<F>;
+}
+
+/// `StdClock` is a [`Clock`] for [`std::time::Instant`].
+#[cfg(feature = "std")]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct StdClock;
+
+#[cfg(feature = "std")]
+impl<F: Float> Clock<F> for StdClock {
+    type Instant = std::time::Instant;
+
+    fn elapsed(&self, earlier: Self::Instant, later: Self::Instant) -> Option<F> {
+        later
+            .checked_duration_since(earlier)
+            .filter(|duration| !duration.is_zero())
+            .map(|duration| F::from_f64(duration.as_secs_f64()))
+    }
+}
+
+/// `TickClock` is a [`Clock`] for tick counters that wrap around, such as hardware timers.
+///
+/// The difference between two tick counts is taken modulo the counter range,
+/// so a counter that wrapped around once between them still gives the elapsed time.
+/// A difference of more than half the range is taken as a timestamp going backwards.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TickClock<F = f32> {
+    period: F,
+    mask: u32,
+}
+
+impl<F: Float> TickClock<F> {
+    /// Creates a new `TickClock` for a 32-bit counter with the specified tick period in seconds.
+    pub fn new(period: F) -> Self {
+        Self {
+            period,
+            mask: u32::MAX,
+        }
+    }
+
+    /// Returns a new `TickClock` for a counter with the specified number of bits, from 1 to 32.
+    pub fn with_bits(self, bits: u32) -> Self {
+        debug_assert!((1..=32).contains(&bits), "bits must be from 1 to 32");
+        Self {
+            mask: u32::MAX >> (32 - bits),
+            ..self
+        }
+    }
+
+    /// Returns the tick period in seconds.
+    pub fn period(&self) -> F {
+        self.period
+    }
+}
+
+impl<F: Float> Clock<F> for TickClock<F> {
+    type Instant = u32;
+
+    fn elapsed(&self, earlier: u32, later: u32) -> Option<F> {
+        let ticks = later.wrapping_sub(earlier) & self.mask;
+        if ticks == 0 || ticks > self.mask / 2 {
+            None
+        } else {
+            Some(F::from_f64(f64::from(ticks)) * self.period)
+        }
+    }
+}
+
+/// `Clocked` is a combinator that updates a controller from timestamps instead of time deltas.
+///
+/// - The first sample has no previous timestamp.
+///   The controller is updated with the time delta set by [`Self::with_first_dt()`] if any;
+///   otherwise the sample only starts the clock, and the held output, initially zero, is returned.
+/// - A timestamp that is not after the previous one does not update the controller, and the held output is returned.
+///   It becomes the reference for the next sample, so a clock that jumped backwards is followed from there on.
+#[derive(Debug, Clone)]
+pub struct Clocked<C, K: Clock<F>, F = f32> {
+    controller: C,
+    clock: K,
+    first_dt: F,
+    last: Option<K::Instant>,
+    output: F,
+}
+
+impl<C, K, F> Clocked<C, K, F>
+where
+    C: PidController<F>,
+    K: Clock<F>,
+    F: Float,
+{
+    /// Creates a new `Clocked` with the specified controller and clock.
+    pub fn new(controller: C, clock: K) -> Self {
+        Self {
+            controller,
+            clock,
+            first_dt: F::NAN,
+            last: None,
+            output: F::ZERO,
+        }
+    }
+
+    /// Returns a new `Clocked` that updates the controller on the first sample with the specified time delta,
+    /// typically the nominal sample period.
+    pub fn with_first_dt(self, first_dt: F) -> Self {
+        Self { first_dt, ..self }
+    }
+
+    /// Updates the controller with the specified set point, actual value, and timestamp.
+    /// Returns the controller output.
+    pub fn update_at(&mut self, set_point: F, actual: F, timestamp: K::Instant) -> F {
+        self.update_at_with_feedforward(set_point, actual, F::ZERO, timestamp)
+    }
+
+    /// Updates the controller with the specified set point, actual value, feedforward value, and timestamp.
+    /// Returns the controller output.
+    pub fn update_at_with_feedforward(
+        &mut self,
+        set_point: F,
+        actual: F,
+        feedforward: F,
+        timestamp: K::Instant,
+    ) -> F {
+        let dt = match self.last {
+            Some(last) => self.clock.elapsed(last, timestamp),
+            None if self.first_dt > F::ZERO => Some(self.first_dt),
+            None => None,
+        };
+        self.last = Some(timestamp);
+        if let Some(dt) = dt {
+            self.output =
+                self.controller
+                    .update_with_feedforward(set_point, actual, feedforward, dt);
+        }
+        self.output
+    }
+
+    /// Forgets the previous timestamp, so the next sample is handled as the first one.
+    /// Call it after the updates have been paused, so that the pause is not taken as a time delta.
+    pub fn reset(&mut self) {
+        self.last = None;
+    }
+
+    /// Returns the timestamp of the previous sample, or `None` before the first sample.
+    pub fn last_timestamp(&self) -> Option<K::Instant> {
+        self.last
+    }
+
+    /// Returns the output returned by the last update.
+    pub fn output(&self) -> F {
+        self.output
+    }
+
+    /// Returns a reference to the controller.
+    pub fn controller(&self) -> &C {
+        &self.controller
+    }
+
+    /// Returns a mutable reference to the controller.
+    pub fn controller_mut(&mut self) -> &mut C {
+        &mut self.controller
+    }
+
+    /// Returns a reference to the clock.
+    pub fn clock(&self) -> &K {
+        &self.clock
+    }
+
+    /// Consumes the `Clocked` and returns the controller.
+    pub fn into_inner(self) -> C {
+        self.controller
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Pid, PidConfig, VelPid};
+
+    #[test]
+    fn test_tick_clock_wrap_around() {
+        let clock: TickClock = TickClock::new(0.5).with_bits(16);
+        assert_eq!(clock.elapsed(10, 14), Some(2.0));
+        assert_eq!(clock.elapsed(65534, 2), Some(2.0));
+        // Higher bits are ignored.
+        assert_eq!(clock.elapsed(0x1_0000, 0x2_0001), Some(0.5));
+        assert_eq!(clock.elapsed(14, 14), None);
+        assert_eq!(clock.elapsed(14, 10), None);
+
+        let clock: TickClock = TickClock::new(0.25);
+        assert_eq!(clock.elapsed(u32::MAX, 9), Some(2.5));
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_std_clock() {
+        use std::time::{Duration, Instant};
+
+        let start = Instant::now();
+        let later = start + Duration::from_millis(250);
+        assert_eq!(Clock::<f64>::elapsed(&StdClock, start, later), Some(0.25));
+        assert_eq!(Clock::<f64>::elapsed(&StdClock, later, start), None);
+        assert_eq!(Clock::<f64>::elapsed(&StdClock, start, start), None);
+    }
+
+    #[test]
+    fn test_clocked_matches_update() {
+        let config = PidConfig::new(1.0, 0.5, 0.25);
+        let mut pid = Pid::new(config.clone());
+        let mut clocked =
+            Clocked::new(Pid::new(config), TickClock::new(0.25).with_bits(8)).with_first_dt(0.25);
+
+        let samples = [(250, 0.0), (251, 0.2), (253, 0.5), (0, 0.7), (2, 0.9)];
+        for (timestamp, actual) in samples {
+            let dt = clocked.last_timestamp().map_or(0.25, |last| {
+                clocked.clock().elapsed(last, timestamp).unwrap()
+            });
+            assert_eq!(
+                clocked.update_at(1.0, actual, timestamp),
+                pid.update(1.0, actual, dt)
+            );
+        }
+    }
+
+    #[test]
+    fn test_clocked_first_sample() {
+        let config = PidConfig::new(1.0, 1.0, 0.0);
+        let mut clocked = Clocked::new(VelPid::new(config), TickClock::new(1.0));
+
+        // The first sample only starts the clock.
+        assert_eq!(clocked.update_at(1.0, 0.0, 100), 0.0);
+        assert_eq!(clocked.update_at(1.0, 0.0, 101), 2.0);
+
+        // After a pause, the next sample starts the clock again.
+        clocked.reset();
+        assert_eq!(clocked.update_at(1.0, 0.0, 1000), 2.0);
+        assert_eq!(clocked.update_at(1.0, 0.0, 1001), 3.0);
+    }
+
+    #[test]
+    fn test_clocked_non_monotonic() {
+        let config = PidConfig::new(0.0, 1.0, 0.0);
+        let mut clocked = Clocked::new(Pid::new(config), TickClock::new(1.0)).with_first_dt(1.0);
+
+        assert_eq!(clocked.update_at(1.0, 0.0, 10), 1.0);
+        assert_eq!(clocked.update_at(1.0, 0.0, 12), 3.0);
+        // A repeated or earlier timestamp holds the output.
+        assert_eq!(clocked.update_at(1.0, 0.0, 12), 3.0);
+        assert_eq!(clocked.update_at(1.0, 0.0, 5), 3.0);
+        // The time is measured from the earlier timestamp from then on.
+        assert_eq!(clocked.update_at(1.0, 0.0, 6), 4.0);
+    }
+}
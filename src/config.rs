@@ -2,65 +2,788 @@
 //!
 //! [`Gain`] is a structure that holds the proportional, integral, and derivative gains for a PID controller.
 //! [`Config`] is a structure that holds a [`Gain`] and also provides optional limits for the controller output.
+//! [`AntiWindup`] selects how the integral term behaves while the output is saturated.
+//! [`DerivativeFilter`] selects the low-pass filter applied to the derivative term.
+//! [`SetpointWeight`] holds the setpoint weights used by the [`TwoDofPid`](crate::TwoDofPid) controller.
+//! [`OutputDeadband`] selects how small outputs are rounded.
+//! [`Discretization`] selects how the integral and the filtered derivative are approximated between samples.
+//! [`StandardGain`] and [`SeriesGain`] hold the gains of the standard (ISA) and series forms, which convert to and from [`Gain`].
+//! [`ConfigError`] is returned by [`Config::validate()`] and the fallible constructors such as [`Config::try_new()`].
 //!
 //! # Examples
 //!
 //! ```rust
-//! use advanced_pid::config::{Config, Gain};
+//! use advanced_pid::config::{AntiWindup, Config, DerivativeFilter, Gain, OutputDeadband};
 //!
 //! let gain = Gain { kp: 1.0, ki: 0.1, kd: 0.1 };
 //! let config = Config::from(gain);
 //!
 //! let config_with_limits = Config::new(1.0, 0.1, 0.1).with_limits(-1.0, 1.0);
+//!
+//! let config_with_anti_windup = Config::new(1.0, 0.1, 0.1)
+//!     .with_limits(-1.0, 1.0)
+//!     .with_anti_windup(AntiWindup::BackCalculation { kt: 1.0 })
+//!     .with_integral_limits(-0.5, 0.5);
+//!
+//! let config_with_filter = Config::new(1.0, 0.1, 0.1).with_derivative_filter(DerivativeFilter::Divisor(10.0));
+//!
+//! let config_with_deadbands = Config::new(1.0, 0.1, 0.1)
+//!     .with_error_deadband(0.01, true)
+//!     .with_output_deadband(OutputDeadband::Zero(0.05));
 //! ```
-use super::FloatType;
+use super::float::{sqrt, tan};
+use super::Float;
 
 /// `Gain` holds the proportional, integral, and derivative gains for a PID controller.
 ///
 /// See also: [`Config`]
 #[derive(Debug, Clone, Default)]
-pub struct Gain {
-    pub kp: FloatType,
-    pub ki: FloatType,
-    pub kd: FloatType,
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Gain<F = f32> {
+    pub kp: F,
+    pub ki: F,
+    pub kd: F,
+}
+
+/// `StandardGain` holds the gains of the standard (ISA, non-interacting) form `Kc (1 + 1 / (Ti s) + Td s)`.
+///
+/// An infinite integral time disables the integral action.
+/// It converts to and from the parallel form [`Gain`] and the series form [`SeriesGain`] with [`TryFrom`].
+///
+/// See also: [`Config::from_standard()`]
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct StandardGain<F = f32> {
+    /// Controller gain `Kc`.
+    pub kc: F,
+    /// Integral time `Ti`, in seconds.
+    pub ti: F,
+    /// Derivative time `Td`, in seconds.
+    pub td: F,
+}
+
+/// `SeriesGain` holds the gains of the series (interacting) form `Kc (1 + 1 / (Ti s)) (1 + Td s)`.
+///
+/// An infinite integral time disables the integral action.
+/// Every series controller has a standard equivalent, but only standard controllers with `Ti >= 4 Td` have a series equivalent.
+///
+/// See also: [`Config::from_series()`]
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SeriesGain<F = f32> {
+    /// Controller gain `Kc`.
+    pub kc: F,
+    /// Integral time `Ti`, in seconds.
+    pub ti: F,
+    /// Derivative time `Td`, in seconds.
+    pub td: F,
+}
+
+/// Checks that the integral time is positive, possibly infinite, and that the derivative time is finite and non-negative.
+fn check_times<F: Float>(ti: F, td: F) -> Result<(), ConfigError> {
+    if ti > F::ZERO && td.is_finite() && td >= F::ZERO {
+        Ok(())
+    } else {
+        Err(ConfigError::InvalidParameter)
+    }
+}
+
+impl<F: Float> Gain<F> {
+    /// Checks that the gains are finite and non-negative, as [`Config::validate()`] does.
+    pub fn validate(&self) -> Result<(), ConfigError> {
+        for gain in [self.kp, self.ki, self.kd] {
+            if !gain.is_finite() {
+                return Err(ConfigError::NonFiniteGain);
+            }
+            if gain < F::ZERO {
+                return Err(ConfigError::NegativeGain);
+            }
+        }
+        Ok(())
+    }
+}
+
+impl<F: Float> TryFrom<StandardGain<F>> for Gain<F> {
+    type Error = ConfigError;
+
+    /// Converts the standard form into the parallel form.
+    fn try_from(gain: StandardGain<F>) -> Result<Self, Self::Error> {
+        check_times(gain.ti, gain.td)?;
+        Ok(Self {
+            kp: gain.kc,
+            ki: gain.kc / gain.ti,
+            kd: gain.kc * gain.td,
+        })
+    }
+}
+
+impl<F: Float> TryFrom<Gain<F>> for StandardGain<F> {
+    type Error = ConfigError;
+
+    /// Converts the parallel form into the standard form.
+    /// Fails with [`ConfigError::ZeroProportionalGain`] if `kp` is zero while `ki` or `kd` is not.
+    fn try_from(gain: Gain<F>) -> Result<Self, Self::Error> {
+        if gain.kp == F::ZERO && (gain.ki != F::ZERO || gain.kd != F::ZERO) {
+            return Err(ConfigError::ZeroProportionalGain);
+        }
+        let ti = if gain.ki == F::ZERO {
+            F::INFINITY
+        } else {
+            gain.kp / gain.ki
+        };
+        let td = if gain.kd == F::ZERO {
+            F::ZERO
+        } else {
+            gain.kd / gain.kp
+        };
+        check_times(ti, td)?;
+        Ok(Self {
+            kc: gain.kp,
+            ti,
+            td,
+        })
+    }
+}
+
+impl<F: Float> TryFrom<SeriesGain<F>> for StandardGain<F> {
+    type Error = ConfigError;
+
+    /// Converts the series form into the standard form.
+    fn try_from(gain: SeriesGain<F>) -> Result<Self, Self::Error> {
+        check_times(gain.ti, gain.td)?;
+        // Written so that an infinite integral time needs no special case.
+        let factor = F::ONE + gain.td / gain.ti;
+        Ok(Self {
+            kc: gain.kc * factor,
+            ti: gain.ti + gain.td,
+            td: gain.td / factor,
+        })
+    }
+}
+
+impl<F: Float> TryFrom<StandardGain<F>> for SeriesGain<F> {
+    type Error = ConfigError;
+
+    /// Converts the standard form into the series form.
+    /// Fails with [`ConfigError::ComplexZeros`] if `Ti < 4 Td`, as the controller zeros are then complex.
+    fn try_from(gain: StandardGain<F>) -> Result<Self, Self::Error> {
+        check_times(gain.ti, gain.td)?;
+        let discriminant = F::ONE - F::from_f64(4.0) * gain.td / gain.ti;
+        if discriminant < F::ZERO {
+            return Err(ConfigError::ComplexZeros);
+        }
+        let half = (F::ONE + sqrt(discriminant)) / F::from_f64(2.0);
+        Ok(Self {
+            kc: gain.kc * half,
+            ti: gain.ti * half,
+            td: gain.td / half,
+        })
+    }
+}
+
+impl<F: Float> TryFrom<SeriesGain<F>> for Gain<F> {
+    type Error = ConfigError;
+
+    /// Converts the series form into the parallel form.
+    fn try_from(gain: SeriesGain<F>) -> Result<Self, Self::Error> {
+        StandardGain::try_from(gain)?.try_into()
+    }
+}
+
+impl<F: Float> TryFrom<Gain<F>> for SeriesGain<F> {
+    type Error = ConfigError;
+
+    /// Converts the parallel form into the series form.
+    fn try_from(gain: Gain<F>) -> Result<Self, Self::Error> {
+        StandardGain::try_from(gain)?.try_into()
+    }
+}
+
+/// `AntiWindup` selects how the integral term is kept from winding up while the output is saturated.
+///
+/// It is honoured by the positional controllers ([`Pid`](crate::Pid), [`PiD`](crate::PiD) and [`Ipd`](crate::Ipd)).
+/// Integrator limits are configured separately with [`Config::with_integral_limits()`] and apply with every strategy.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum AntiWindup<F = f32> {
+    /// The integral term keeps accumulating while the output is saturated.
+    #[default]
+    None,
+    /// Conditional integration (clamping).
+    /// The integral term stops at the output limit while the error drives the output further into saturation.
+    Conditional,
+    /// Back-calculation.
+    /// The integral term is pulled back by `kt * (output - unclamped_output)` per second while the output is saturated.
+    BackCalculation {
+        /// Tracking gain in 1/s.
+        kt: F,
+    },
+}
+
+/// `DerivativeFilter` selects the first-order low-pass filter applied to the derivative term.
+///
+/// The filter is discretised with the `dt` passed to `update`, so its cut-off frequency does not depend on the sample time,
+/// except for [`Self::Smoothing`].
+/// It is honoured by every controller.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum DerivativeFilter<F = f32> {
+    /// The derivative term is not filtered.
+    #[default]
+    None,
+    /// Filter with the specified time constant in seconds.
+    TimeConstant(F),
+    /// Filter with the time constant `Td / N`, where `Td = kd / kp` is the derivative time and `N` is the specified divisor.
+    /// The filter is bypassed while `kp` is zero.
+    Divisor(F),
+    /// Exponential smoothing that moves the filter output by the specified fraction, from 0 to 1, of the way to the derivative on every update.
+    /// Its cut-off frequency depends on the sample time.
+    Smoothing(F),
+}
+
+impl<F: Float> DerivativeFilter<F> {
+    /// Returns the filter that [`VelPid`](crate::VelPid) always applied before version 0.3.0, `Smoothing(0.125)`.
+    /// ```
+    /// use advanced_pid::config::DerivativeFilter;
+    ///
+    /// assert_eq!(DerivativeFilter::vel_pid_legacy(), DerivativeFilter::Smoothing(0.125));
+    /// ```
+    pub fn vel_pid_legacy() -> Self {
+        Self::Smoothing(F::from_f64(0.125))
+    }
+}
+
+/// `SetpointWeight` holds the setpoint weights of a two-degree-of-freedom PID controller.
+///
+/// The proportional action acts on `b * set_point - actual` and the derivative action on `c * set_point - actual`.
+/// It is used by [`TwoDofPid`](crate::TwoDofPid); the other controllers have fixed weights.
+///
+/// See also: [`Config`]
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SetpointWeight<F = f32> {
+    pub b: F,
+    pub c: F,
+}
+
+impl<F: Float> Default for SetpointWeight<F> {
+    /// Creates weights that make [`TwoDofPid`](crate::TwoDofPid) behave like [`Pid`](crate::Pid).
+    fn default() -> Self {
+        Self {
+            b: F::ONE,
+            c: F::ONE,
+        }
+    }
+}
+
+/// `OutputDeadband` selects how outputs smaller in magnitude than a threshold are rounded.
+///
+/// It is applied to the returned output only, after the limits and rate limits,
+/// so that the controller's state keeps accumulating small changes.
+/// It is honoured by every controller.
+///
+/// The rate limits apply to the output before the deadband, so the returned output jumps by up to the threshold
+/// when it leaves the deadband, however low the rates are.
+/// Limiting the rate of the rounded output instead would keep it in the deadband forever
+/// whenever the change allowed in one update is smaller than the threshold.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum OutputDeadband<F = f32> {
+    /// Outputs are not rounded.
+    #[default]
+    None,
+    /// Outputs smaller in magnitude than the threshold are rounded to zero.
+    Zero(F),
+    /// Outputs smaller in magnitude than the threshold are rounded to the nearest of zero and the threshold with the same sign,
+    /// where the threshold is the minimum effective output of the actuator.
+    Nearest(F),
+}
+
+impl<F: Float> OutputDeadband<F> {
+    /// Returns `output` rounded according to the deadband.
+    pub(crate) fn apply(&self, output: F) -> F {
+        match *self {
+            OutputDeadband::Zero(threshold) if output.abs() < threshold => F::ZERO,
+            OutputDeadband::Nearest(threshold) if output.abs() < threshold => {
+                if output.abs() + output.abs() < threshold {
+                    F::ZERO
+                } else if output < F::ZERO {
+                    -threshold
+                } else {
+                    threshold
+                }
+            }
+            _ => output,
+        }
+    }
+}
+
+/// `Discretization` selects how the integral and the filtered derivative are approximated between samples.
+///
+/// It is honoured by the floating point controllers.
+/// On the first update, the previous error is taken equal to the current one.
+/// The derivative without a filter is always the backward difference `(e(k) − e(k−1)) / dt`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Discretization {
+    /// Forward Euler: `s ≈ (z − 1) / dt`.
+    /// The integral uses the previous error, and the derivative filter is unstable if `dt` exceeds twice its time constant.
+    ForwardEuler,
+    /// Backward Euler: `s ≈ (z − 1) / (z dt)`.
+    /// The integral uses the current error, and the derivative filter is stable for any `dt`.
+    #[default]
+    BackwardEuler,
+    /// Tustin (trapezoidal): `s ≈ 2 (z − 1) / (dt (z + 1))`.
+    /// The integral uses the average of the current and previous errors, which is exact for ramps.
+    Tustin,
+    /// Tustin with prewarping at the cut-off frequency of the derivative filter,
+    /// so that the discrete derivative matches the continuous one exactly at that frequency.
+    /// The integral is the same as with [`Self::Tustin`].
+    /// Prewarping is skipped if the cut-off frequency is above the Nyquist frequency.
+    TustinPrewarped,
+}
+
+/// `ConfigError` is the reason why a [`Config`] is invalid.
+///
+/// See also: [`Config::validate()`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConfigError {
+    /// A gain is NaN or infinite.
+    NonFiniteGain,
+    /// A gain is negative.
+    /// Reverse-acting processes are controlled by swapping the set point and the actual value instead.
+    NegativeGain,
+    /// A limit is NaN.
+    NanLimit,
+    /// The lower output limit is greater than the upper output limit.
+    InvertedLimits,
+    /// The lower integral limit is greater than the upper integral limit.
+    InvertedIntegralLimits,
+    /// A rate limit is zero, negative, or NaN.
+    InvalidRateLimit,
+    /// The back-calculation gain, the derivative filter, a deadband, a setpoint weight,
+    /// or the integral or derivative time of a [`StandardGain`] or [`SeriesGain`] is out of range.
+    InvalidParameter,
+    /// The gains cannot be converted from the parallel form because `kp` is zero while `ki` or `kd` is not.
+    ZeroProportionalGain,
+    /// The gains cannot be converted into the series form because `Ti < 4 Td`.
+    ComplexZeros,
+}
+
+impl core::fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        let message = match self {
+            Self::NonFiniteGain => "gains must be finite",
+            Self::NegativeGain => "gains must not be negative",
+            Self::NanLimit => "limits must not be NaN",
+            Self::InvertedLimits => {
+                "the lower output limit must not be greater than the upper limit"
+            }
+            Self::InvertedIntegralLimits => {
+                "the lower integral limit must not be greater than the upper limit"
+            }
+            Self::InvalidRateLimit => "rate limits must be positive",
+            Self::InvalidParameter => "parameters must be finite and non-negative",
+            Self::ZeroProportionalGain => {
+                "the standard and series forms require a non-zero proportional gain"
+            }
+            Self::ComplexZeros => {
+                "the series form requires an integral time of at least four derivative times"
+            }
+        };
+        f.write_str(message)
+    }
 }
 
+#[cfg(feature = "std")]
+impl std::error::Error for ConfigError {}
+
 /// `Config` holds a [`Gain`] and also provides optional limits for the controller output.
+///
+/// `i_min` and `i_max` limit the integral contribution to the output, independently of `min` and `max`.
+/// `rise_rate` and `fall_rate` limit how fast the output may rise and fall in units per second, after clamping to `min` and `max`.
+/// While the magnitude of the error is within `error_deadband`, the controller acts as if the actual value was at the set point.
+/// `freeze_integral` selects whether the integral term stops inside the error deadband or keeps integrating the true error.
+/// `discretization` selects how the integral and the filtered derivative are approximated between samples.
 #[derive(Debug, Clone)]
-pub struct Config {
-    pub gain: Gain,
-    pub min: FloatType,
-    pub max: FloatType,
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(
+    feature = "serde",
+    serde(
+        default,
+        bound(
+            serialize = "F: Float + serde::Serialize",
+            deserialize = "F: Float + serde::Deserialize<'de>"
+        )
+    )
+)]
+pub struct Config<F = f32> {
+    pub gain: Gain<F>,
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_float::neg_infinity"))]
+    pub min: F,
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_float::infinity"))]
+    pub max: F,
+    pub anti_windup: AntiWindup<F>,
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_float::neg_infinity"))]
+    pub i_min: F,
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_float::infinity"))]
+    pub i_max: F,
+    pub d_filter: DerivativeFilter<F>,
+    pub weight: SetpointWeight<F>,
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_float::infinity"))]
+    pub rise_rate: F,
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_float::infinity"))]
+    pub fall_rate: F,
+    pub error_deadband: F,
+    pub freeze_integral: bool,
+    pub output_deadband: OutputDeadband<F>,
+    pub discretization: Discretization,
 }
 
-impl Default for Config {
+impl<F: Float> Default for Config<F> {
     fn default() -> Self {
         Self {
             gain: Default::default(),
-            min: FloatType::NEG_INFINITY,
-            max: FloatType::INFINITY,
+            min: F::NEG_INFINITY,
+            max: F::INFINITY,
+            anti_windup: Default::default(),
+            i_min: F::NEG_INFINITY,
+            i_max: F::INFINITY,
+            d_filter: Default::default(),
+            weight: Default::default(),
+            rise_rate: F::INFINITY,
+            fall_rate: F::INFINITY,
+            error_deadband: F::ZERO,
+            freeze_integral: true,
+            output_deadband: Default::default(),
+            discretization: Default::default(),
         }
     }
 }
 
-impl Config {
+impl<F: Float> Config<F> {
     /// Creates a new `Config` with the specified gains.
-    pub fn new(kp: FloatType, ki: FloatType, kd: FloatType) -> Self {
+    pub fn new(kp: F, ki: F, kd: F) -> Self {
         Self {
             gain: Gain { kp, ki, kd },
             ..Default::default()
         }
     }
 
+    /// Creates a new `Config` with the specified gains, which must be finite and non-negative.
+    ///
+    /// See also: [`Self::validate()`]
+    pub fn try_new(kp: F, ki: F, kd: F) -> Result<Self, ConfigError> {
+        let config = Self::new(kp, ki, kd);
+        config.validate()?;
+        Ok(config)
+    }
+
+    /// Creates a new `Config` from the gains of the standard (ISA) form, if it is [valid](Self::validate()).
+    /// An infinite integral time disables the integral action.
+    /// ```
+    /// use advanced_pid::config::Config;
+    ///
+    /// let config: Config = Config::from_standard(2.0, 4.0, 0.5).unwrap();
+    /// assert_eq!((config.gain.kp, config.gain.ki, config.gain.kd), (2.0, 0.5, 1.0));
+    /// ```
+    pub fn from_standard(kc: F, ti: F, td: F) -> Result<Self, ConfigError> {
+        let config: Self = Gain::try_from(StandardGain { kc, ti, td })?.into();
+        config.validate()?;
+        Ok(config)
+    }
+
+    /// Creates a new `Config` from the gains of the series (interacting) form, if it is [valid](Self::validate()).
+    /// An infinite integral time disables the integral action.
+    pub fn from_series(kc: F, ti: F, td: F) -> Result<Self, ConfigError> {
+        let config: Self = Gain::try_from(SeriesGain { kc, ti, td })?.into();
+        config.validate()?;
+        Ok(config)
+    }
+
     /// Returns a new `Config` with the specified limits.
-    pub fn with_limits(self, min: FloatType, max: FloatType) -> Self {
+    pub fn with_limits(self, min: F, max: F) -> Self {
         Self { min, max, ..self }
     }
+
+    /// Returns a new `Config` with the specified limits, which must not be NaN, with `min` not greater than `max`.
+    /// Infinite limits leave the output unbounded.
+    pub fn try_with_limits(self, min: F, max: F) -> Result<Self, ConfigError> {
+        check_limits(min, max, ConfigError::InvertedLimits)?;
+        Ok(self.with_limits(min, max))
+    }
+
+    /// Returns a new `Config` with the specified anti-windup strategy.
+    pub fn with_anti_windup(self, anti_windup: AntiWindup<F>) -> Self {
+        Self {
+            anti_windup,
+            ..self
+        }
+    }
+
+    /// Returns a new `Config` with the specified limits for the integral contribution.
+    pub fn with_integral_limits(self, i_min: F, i_max: F) -> Self {
+        Self {
+            i_min,
+            i_max,
+            ..self
+        }
+    }
+
+    /// Returns a new `Config` with the specified derivative filter.
+    pub fn with_derivative_filter(self, d_filter: DerivativeFilter<F>) -> Self {
+        Self { d_filter, ..self }
+    }
+
+    /// Returns a new `Config` with the specified setpoint weights for the proportional (`b`) and derivative (`c`) actions.
+    pub fn with_setpoint_weights(self, b: F, c: F) -> Self {
+        Self {
+            weight: SetpointWeight { b, c },
+            ..self
+        }
+    }
+
+    /// Returns a new `Config` with the specified rate limits for the output in units per second.
+    /// Both rates are magnitudes, so `fall_rate` is positive as well.
+    /// They limit the output before the [output deadband](OutputDeadband), which can make the returned output jump.
+    pub fn with_rate_limits(self, rise_rate: F, fall_rate: F) -> Self {
+        Self {
+            rise_rate,
+            fall_rate,
+            ..self
+        }
+    }
+
+    /// Returns a new `Config` with the specified error deadband.
+    /// If `freeze_integral` is `true`, the integral term stops inside the deadband,
+    /// otherwise it keeps integrating the true error.
+    pub fn with_error_deadband(self, error_deadband: F, freeze_integral: bool) -> Self {
+        Self {
+            error_deadband,
+            freeze_integral,
+            ..self
+        }
+    }
+
+    /// Returns a new `Config` with the specified output deadband.
+    pub fn with_output_deadband(self, output_deadband: OutputDeadband<F>) -> Self {
+        Self {
+            output_deadband,
+            ..self
+        }
+    }
+
+    /// Returns a new `Config` with the specified discretisation method.
+    pub fn with_discretization(self, discretization: Discretization) -> Self {
+        Self {
+            discretization,
+            ..self
+        }
+    }
+
+    /// Returns the time constant of the derivative filter in seconds.
+    /// It is zero without a filter, and with [`DerivativeFilter::Smoothing`], whose time constant depends on the sample time.
+    pub fn derivative_time_constant(&self) -> F {
+        match self.d_filter {
+            DerivativeFilter::None | DerivativeFilter::Smoothing(_) => F::ZERO,
+            DerivativeFilter::TimeConstant(tf) => tf,
+            DerivativeFilter::Divisor(_) if self.gain.kp == F::ZERO => F::ZERO,
+            DerivativeFilter::Divisor(n) => self.gain.kd / (self.gain.kp * n),
+        }
+    }
+
+    /// Checks that the configuration is consistent.
+    ///
+    /// The gains must be finite and non-negative, the limits must not be NaN and must be ordered,
+    /// the rate limits must be positive, the smoothing factor of the derivative filter must be at most one,
+    /// and the remaining parameters must be finite and non-negative,
+    /// except the setpoint weights, which may be negative.
+    /// ```
+    /// use advanced_pid::config::{Config, ConfigError};
+    ///
+    /// assert_eq!(Config::new(1.0, 0.1, 0.0).with_limits(1.0, -1.0).validate(), Err(ConfigError::InvertedLimits));
+    /// assert_eq!(Config::new(1.0, f32::NAN, 0.0).validate(), Err(ConfigError::NonFiniteGain));
+    /// ```
+    pub fn validate(&self) -> Result<(), ConfigError> {
+        self.gain.validate()?;
+        check_limits(self.min, self.max, ConfigError::InvertedLimits)?;
+        check_limits(self.i_min, self.i_max, ConfigError::InvertedIntegralLimits)?;
+        let positive = |rate: F| rate > F::ZERO;
+        if !positive(self.rise_rate) || !positive(self.fall_rate) {
+            return Err(ConfigError::InvalidRateLimit);
+        }
+        let kt = match self.anti_windup {
+            AntiWindup::BackCalculation { kt } => kt,
+            _ => F::ZERO,
+        };
+        let filter = match self.d_filter {
+            DerivativeFilter::None => F::ZERO,
+            DerivativeFilter::TimeConstant(value) | DerivativeFilter::Divisor(value) => value,
+            DerivativeFilter::Smoothing(alpha) => alpha,
+        };
+        let threshold = match self.output_deadband {
+            OutputDeadband::None => F::ZERO,
+            OutputDeadband::Zero(value) | OutputDeadband::Nearest(value) => value,
+        };
+        if matches!(self.d_filter, DerivativeFilter::Smoothing(alpha) if alpha > F::ONE) {
+            return Err(ConfigError::InvalidParameter);
+        }
+        let non_negative = [kt, filter, threshold, self.error_deadband];
+        let weights = [self.weight.b, self.weight.c];
+        if non_negative
+            .iter()
+            .any(|value| !value.is_finite() || *value < F::ZERO)
+            || weights.iter().any(|value| !value.is_finite())
+        {
+            return Err(ConfigError::InvalidParameter);
+        }
+        Ok(())
+    }
+
+    /// Feeds the raw derivative `d_term` into the filter state `lpf` and returns the filtered derivative.
+    pub(crate) fn filter_derivative(&self, lpf: &mut F, d_term: F, dt: F) -> F {
+        if let DerivativeFilter::Smoothing(alpha) = self.d_filter {
+            *lpf += (d_term - *lpf) * alpha;
+            return *lpf;
+        }
+        let tf = self.derivative_time_constant();
+        if tf <= F::ZERO {
+            *lpf = d_term;
+            return *lpf;
+        }
+        let two = F::from_f64(2.0);
+        match self.discretization {
+            Discretization::ForwardEuler => *lpf += (d_term - *lpf) * dt / tf,
+            Discretization::BackwardEuler => *lpf += (d_term - *lpf) * dt / (tf + dt),
+            Discretization::Tustin => *lpf += (d_term - *lpf) * two * dt / (two * tf + dt),
+            Discretization::TustinPrewarped => {
+                // Prewarping replaces `dt` with `2 tan(ω dt / 2) / ω` at the cut-off frequency `ω = 1 / tf`.
+                let half_angle = dt / (two * tf);
+                let warped = if half_angle < F::from_f64(core::f64::consts::FRAC_PI_2) {
+                    two * tf * tan(half_angle)
+                } else {
+                    dt
+                };
+                let d_term = d_term * dt / warped;
+                *lpf += (d_term - *lpf) * two * warped / (two * tf + warped);
+            }
+        }
+        *lpf
+    }
+
+    /// Returns the error to integrate over the last sample interval, from the current and previous errors.
+    /// The previous error is NaN before the first update.
+    pub(crate) fn integral_error(&self, error: F, pre_error: F) -> F {
+        let pre_error = if pre_error.is_nan() { error } else { pre_error };
+        match self.discretization {
+            Discretization::ForwardEuler => pre_error,
+            Discretization::BackwardEuler => error,
+            Discretization::Tustin | Discretization::TustinPrewarped => {
+                (error + pre_error) / F::from_f64(2.0)
+            }
+        }
+    }
+
+    /// Applies the error deadband to `actual`.
+    /// Returns the actual value seen by the controller, which is the set point inside the deadband,
+    /// and the error to integrate.
+    /// A NaN error is outside the deadband, so that a failed measurement is not mistaken for the set point.
+    pub(crate) fn deadband(&self, set_point: F, actual: F) -> (F, F) {
+        let error = set_point - actual;
+        if error.is_nan() || error.abs() > self.error_deadband {
+            (actual, error)
+        } else if self.freeze_integral {
+            (set_point, F::ZERO)
+        } else {
+            (set_point, error)
+        }
+    }
+
+    /// Returns the limits for the output following `pre_output`, combining `min` and `max` with the rate limits.
+    /// The rate limits take precedence.
+    pub(crate) fn output_limits(&self, pre_output: F, dt: F) -> (F, F) {
+        // Infinite rates are skipped, as they would turn into NaN when `dt` is zero.
+        // So is a non-finite previous output or `dt`, after which the controller recovers instead of panicking,
+        // and a `dt` that is not positive, which would invert the limits.
+        let bounded = pre_output.is_finite() && dt.is_finite() && dt > F::ZERO;
+        let lower = if bounded && self.fall_rate < F::INFINITY {
+            pre_output - self.fall_rate * dt
+        } else {
+            F::NEG_INFINITY
+        };
+        let upper = if bounded && self.rise_rate < F::INFINITY {
+            pre_output + self.rise_rate * dt
+        } else {
+            F::INFINITY
+        };
+        (self.min.clamp(lower, upper), self.max.clamp(lower, upper))
+    }
+
+    /// Adds `delta` to the integral contribution `i_term` according to the anti-windup strategy.
+    /// `others` is the sum of the remaining output contributions, and `limits` are the [output limits](Self::output_limits).
+    /// Returns the limited controller output, and the output before the limits and the anti-windup strategy.
+    pub(crate) fn integrate(
+        &self,
+        i_term: &mut F,
+        delta: F,
+        others: F,
+        dt: F,
+        (min, max): (F, F),
+    ) -> (F, F) {
+        let candidate = (*i_term + delta).clamp(self.i_min, self.i_max);
+        let unclamped = others + candidate;
+        let output = unclamped.clamp(min, max);
+        match self.anti_windup {
+            AntiWindup::None => *i_term = candidate,
+            AntiWindup::Conditional => {
+                // Integrate only up to the limit while the integral pushes the output further into saturation.
+                if (unclamped - output) * delta > F::ZERO {
+                    let limited = output - others;
+                    *i_term = if delta > F::ZERO {
+                        limited.max(*i_term)
+                    } else {
+                        limited.min(*i_term)
+                    };
+                    return ((others + *i_term).clamp(min, max), unclamped);
+                }
+                *i_term = candidate;
+            }
+            AntiWindup::BackCalculation { kt } => {
+                *i_term =
+                    (candidate + kt * (output - unclamped) * dt).clamp(self.i_min, self.i_max);
+            }
+        }
+        (output, unclamped)
+    }
+
+    /// Sets the integral contribution `i_term` so that the output equals the manual `output`.
+    /// `others` is the sum of the remaining output contributions, and `limits` are the [output limits](Self::output_limits).
+    /// Returns the limited manual output, and the manual output before the limits.
+    pub(crate) fn track(&self, i_term: &mut F, output: F, others: F, (min, max): (F, F)) -> (F, F) {
+        let limited = output.clamp(min, max);
+        *i_term = (limited - others).clamp(self.i_min, self.i_max);
+        (limited, output)
+    }
+}
+
+/// Checks that the limits are not NaN and that `min` is not greater than `max`.
+pub(crate) fn check_limits<F: Float>(
+    min: F,
+    max: F,
+    inverted: ConfigError,
+) -> Result<(), ConfigError> {
+    if min.is_nan() || max.is_nan() {
+        Err(ConfigError::NanLimit)
+    } else if min > max {
+        Err(inverted)
+    } else {
+        Ok(())
+    }
 }
 
-impl From<Gain> for Config {
+impl<F: Float> From<Gain<F>> for Config<F> {
     /// Converts a `Gain` into a `Config`.
-    fn from(gain: Gain) -> Self {
+    fn from(gain: Gain<F>) -> Self {
         Self {
             gain,
             ..Default::default()
@@ -74,7 +797,7 @@ mod tests {
 
     #[test]
     fn test_gain_default() {
-        let gain = Gain::default();
+        let gain: Gain = Gain::default();
         assert_eq!(gain.kp, 0.0);
         assert_eq!(gain.ki, 0.0);
         assert_eq!(gain.kd, 0.0);
@@ -86,8 +809,8 @@ mod tests {
         assert_eq!(config.gain.kp, 1.0);
         assert_eq!(config.gain.ki, 0.5);
         assert_eq!(config.gain.kd, 0.1);
-        assert_eq!(config.min, FloatType::NEG_INFINITY);
-        assert_eq!(config.max, FloatType::INFINITY);
+        assert_eq!(config.min, f32::NEG_INFINITY);
+        assert_eq!(config.max, f32::INFINITY);
     }
 
     #[test]
@@ -111,17 +834,352 @@ mod tests {
         assert_eq!(config.gain.kp, 1.0);
         assert_eq!(config.gain.ki, 0.5);
         assert_eq!(config.gain.kd, 0.1);
-        assert_eq!(config.min, FloatType::NEG_INFINITY);
-        assert_eq!(config.max, FloatType::INFINITY);
+        assert_eq!(config.min, f32::NEG_INFINITY);
+        assert_eq!(config.max, f32::INFINITY);
+    }
+
+    #[test]
+    fn test_config_with_anti_windup() {
+        let config = Config::new(1.0, 0.5, 0.1)
+            .with_anti_windup(AntiWindup::Conditional)
+            .with_integral_limits(-0.5, 0.5);
+        assert_eq!(config.anti_windup, AntiWindup::Conditional);
+        assert_eq!(config.i_min, -0.5);
+        assert_eq!(config.i_max, 0.5);
+        assert_eq!(config.min, f32::NEG_INFINITY);
+        assert_eq!(config.max, f32::INFINITY);
+    }
+
+    /// Simulates a step response of the controller on a saturating first-order plant and returns the peak value.
+    fn peak_on_saturating_plant<C: crate::PidController>(anti_windup: AntiWindup) -> f32 {
+        let config = Config::new(2.0, 4.0, 0.0)
+            .with_limits(-1.2, 1.2)
+            .with_anti_windup(anti_windup);
+        let mut pid = C::new(config);
+        let dt = 0.01;
+        let mut actual: f32 = 0.0;
+        let mut peak: f32 = 0.0;
+        for _ in 0..2000 {
+            let output = pid.update(1.0, actual, dt);
+            actual += (output - actual) * dt;
+            peak = peak.max(actual);
+        }
+        peak
+    }
+
+    /// Checks that both anti-windup strategies at least halve the overshoot caused by integrator windup.
+    fn assert_anti_windup<C: crate::PidController>() {
+        let none = peak_on_saturating_plant::<C>(AntiWindup::None);
+        let conditional = peak_on_saturating_plant::<C>(AntiWindup::Conditional);
+        let back_calculation =
+            peak_on_saturating_plant::<C>(AntiWindup::BackCalculation { kt: 2.0 });
+        let name = core::any::type_name::<C>();
+        assert!(none > 1.05, "{}: peak without anti-windup: {}", name, none);
+        for peak in [conditional, back_calculation] {
+            assert!(
+                peak < 1.05 && peak - 1.0 < (none - 1.0) / 2.0,
+                "{}: peak with anti-windup: {}, without: {}",
+                name,
+                peak,
+                none
+            );
+        }
+    }
+
+    #[test]
+    fn test_nan_actual_propagates() {
+        fn assert_nan<C: crate::PidController>(config: Config) {
+            let mut pid = C::new(config);
+            assert!(pid.update(1.0, f32::NAN, 0.1).is_nan());
+        }
+        for config in [
+            Config::new(1.0, 0.5, 0.1),
+            Config::new(1.0, 0.5, 0.1).with_error_deadband(0.1, true),
+        ] {
+            assert_nan::<crate::Pid>(config.clone());
+            assert_nan::<crate::PiD>(config.clone());
+            assert_nan::<crate::Ipd>(config.clone());
+            assert_nan::<crate::TwoDofPid>(config.clone());
+            assert_nan::<crate::VelPid>(config);
+        }
+    }
+
+    #[test]
+    fn test_anti_windup_overshoot() {
+        assert_anti_windup::<crate::Pid>();
+        assert_anti_windup::<crate::PiD>();
+        assert_anti_windup::<crate::Ipd>();
+    }
+
+    #[test]
+    fn test_config_derivative_time_constant() {
+        let config = Config::new(2.0, 0.5, 1.0);
+        assert_eq!(config.derivative_time_constant(), 0.0);
+        let config = config.with_derivative_filter(DerivativeFilter::TimeConstant(0.2));
+        assert_eq!(config.derivative_time_constant(), 0.2);
+        let config = config.with_derivative_filter(DerivativeFilter::Divisor(10.0));
+        assert_eq!(config.derivative_time_constant(), 0.05);
+        let config =
+            Config::new(0.0, 0.5, 1.0).with_derivative_filter(DerivativeFilter::Divisor(10.0));
+        assert_eq!(config.derivative_time_constant(), 0.0);
+    }
+
+    #[test]
+    fn test_config_with_setpoint_weights() {
+        let config = Config::new(1.0, 0.5, 0.1).with_setpoint_weights(0.5, 0.0);
+        assert_eq!(config.weight.b, 0.5);
+        assert_eq!(config.weight.c, 0.0);
+    }
+
+    #[test]
+    fn test_config_output_limits() {
+        let config = Config::new(1.0, 0.5, 0.1)
+            .with_limits(-1.0, 1.0)
+            .with_rate_limits(2.0, 4.0);
+        assert_eq!(
+            Config::new(1.0, 0.5, 0.1).output_limits(0.5, 0.0),
+            (f32::NEG_INFINITY, f32::INFINITY)
+        );
+        assert_eq!(config.output_limits(0.5, 0.25), (-0.5, 1.0));
+        assert_eq!(config.output_limits(0.75, 0.25), (-0.25, 1.0));
+        assert_eq!(config.output_limits(3.0, 0.25), (2.0, 2.0));
+    }
+
+    #[test]
+    fn test_config_deadband() {
+        let config = Config::new(1.0, 0.5, 0.1).with_error_deadband(0.25, true);
+        assert_eq!(config.deadband(1.0, 0.5), (0.5, 0.5));
+        assert_eq!(config.deadband(1.0, 0.875), (1.0, 0.0));
+        let config = config.with_error_deadband(0.25, false);
+        assert_eq!(config.deadband(1.0, 0.875), (1.0, 0.125));
+    }
+
+    #[test]
+    fn test_output_deadband() {
+        assert_eq!(OutputDeadband::None.apply(0.1), 0.1);
+        assert_eq!(OutputDeadband::Zero(0.5).apply(0.3), 0.0);
+        assert_eq!(OutputDeadband::Zero(0.5).apply(-0.6), -0.6);
+        assert_eq!(OutputDeadband::Nearest(0.5).apply(0.2), 0.0);
+        assert_eq!(OutputDeadband::Nearest(0.5).apply(-0.3), -0.5);
+        assert_eq!(OutputDeadband::Nearest(0.5).apply(0.7), 0.7);
     }
 
     #[test]
     fn test_config_default() {
-        let config = Config::default();
+        let config: Config = Config::default();
         assert_eq!(config.gain.kp, 0.0);
         assert_eq!(config.gain.ki, 0.0);
         assert_eq!(config.gain.kd, 0.0);
-        assert_eq!(config.min, FloatType::NEG_INFINITY);
-        assert_eq!(config.max, FloatType::INFINITY);
+        assert_eq!(config.min, f32::NEG_INFINITY);
+        assert_eq!(config.max, f32::INFINITY);
+        assert_eq!(config.anti_windup, AntiWindup::None);
+        assert_eq!(config.i_min, f32::NEG_INFINITY);
+        assert_eq!(config.i_max, f32::INFINITY);
+        assert_eq!(config.d_filter, DerivativeFilter::None);
+        assert_eq!(config.weight, SetpointWeight::default());
+        assert_eq!(config.rise_rate, f32::INFINITY);
+        assert_eq!(config.fall_rate, f32::INFINITY);
+        assert_eq!(config.error_deadband, 0.0);
+        assert!(config.freeze_integral);
+        assert_eq!(config.output_deadband, OutputDeadband::None);
+    }
+
+    #[test]
+    fn test_gain_forms() {
+        let standard = StandardGain {
+            kc: 2.0,
+            ti: 4.0,
+            td: 0.5,
+        };
+        let gain = Gain::try_from(standard).unwrap();
+        assert_eq!((gain.kp, gain.ki, gain.kd), (2.0, 0.5, 1.0));
+        assert_eq!(StandardGain::try_from(gain), Ok(standard));
+
+        // Ti = 4, Td = 0.5 factorizes into (1 + 1 / (Ti' s)) (1 + Td' s) with Ti' = 2 + √2 and Td' = 2 − √2.
+        let series = SeriesGain::try_from(standard).unwrap();
+        let root = core::f64::consts::SQRT_2 as f32;
+        assert!((series.kc - (1.0 + root / 2.0)).abs() < 1e-6);
+        assert!((series.ti - (2.0 + root)).abs() < 1e-6);
+        assert!((series.td - (2.0 - root)).abs() < 1e-6);
+        let back = StandardGain::try_from(series).unwrap();
+        assert!((back.kc - 2.0).abs() < 1e-6);
+        assert!((back.ti - 4.0).abs() < 1e-6);
+        assert!((back.td - 0.5).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_gain_forms_without_integral() {
+        let gain: Gain = Gain {
+            kp: 2.0,
+            ki: 0.0,
+            kd: 1.0,
+        };
+        let standard = StandardGain::try_from(gain.clone()).unwrap();
+        assert_eq!(
+            (standard.kc, standard.ti, standard.td),
+            (2.0, f32::INFINITY, 0.5)
+        );
+        let series = SeriesGain::try_from(gain).unwrap();
+        assert_eq!((series.kc, series.ti, series.td), (2.0, f32::INFINITY, 0.5));
+        let gain = Gain::try_from(series).unwrap();
+        assert_eq!((gain.kp, gain.ki, gain.kd), (2.0, 0.0, 1.0));
+    }
+
+    #[test]
+    fn test_gain_forms_impossible() {
+        let integral_only: Gain = Gain {
+            kp: 0.0,
+            ki: 1.0,
+            kd: 0.0,
+        };
+        assert_eq!(
+            StandardGain::try_from(integral_only),
+            Err(ConfigError::ZeroProportionalGain)
+        );
+        let standard = StandardGain {
+            kc: 1.0,
+            ti: 1.0,
+            td: 0.5,
+        };
+        assert_eq!(
+            SeriesGain::try_from(standard),
+            Err(ConfigError::ComplexZeros)
+        );
+        let standard = StandardGain {
+            ti: 0.0,
+            ..standard
+        };
+        assert_eq!(
+            Gain::try_from(standard).unwrap_err(),
+            ConfigError::InvalidParameter
+        );
+    }
+
+    #[test]
+    fn test_config_from_forms() {
+        let config: Config = Config::from_standard(2.0, f32::INFINITY, 0.0).unwrap();
+        assert_eq!(
+            (config.gain.kp, config.gain.ki, config.gain.kd),
+            (2.0, 0.0, 0.0)
+        );
+        let config: Config = Config::from_series(1.0, 1.0, 1.0).unwrap();
+        assert_eq!(
+            (config.gain.kp, config.gain.ki, config.gain.kd),
+            (2.0, 1.0, 1.0)
+        );
+        assert_eq!(
+            Config::from_standard(-1.0, 1.0, 0.0).unwrap_err(),
+            ConfigError::NegativeGain
+        );
+    }
+
+    #[test]
+    fn test_config_validate() {
+        let config: Config = Config::new(1.0, 0.5, 0.1);
+        assert_eq!(config.validate(), Ok(()));
+        assert_eq!(
+            Config::<f32>::default()
+                .with_integral_limits(-1.0, 1.0)
+                .validate(),
+            Ok(())
+        );
+        assert_eq!(
+            Config::new(-1.0, 0.5, 0.1).validate(),
+            Err(ConfigError::NegativeGain)
+        );
+        assert_eq!(
+            Config::new(1.0, f32::INFINITY, 0.1).validate(),
+            Err(ConfigError::NonFiniteGain)
+        );
+        assert_eq!(
+            config.clone().with_limits(f32::NAN, 1.0).validate(),
+            Err(ConfigError::NanLimit)
+        );
+        assert_eq!(
+            config.clone().with_integral_limits(1.0, -1.0).validate(),
+            Err(ConfigError::InvertedIntegralLimits)
+        );
+        assert_eq!(
+            config.clone().with_rate_limits(1.0, 0.0).validate(),
+            Err(ConfigError::InvalidRateLimit)
+        );
+        assert_eq!(
+            config
+                .clone()
+                .with_anti_windup(AntiWindup::BackCalculation { kt: -1.0 })
+                .validate(),
+            Err(ConfigError::InvalidParameter)
+        );
+        assert_eq!(
+            config
+                .clone()
+                .with_derivative_filter(DerivativeFilter::Smoothing(1.5))
+                .validate(),
+            Err(ConfigError::InvalidParameter)
+        );
+        assert_eq!(
+            config.with_error_deadband(f32::NAN, true).validate(),
+            Err(ConfigError::InvalidParameter)
+        );
+    }
+
+    #[test]
+    fn test_config_try_new() {
+        let config = Config::try_new(1.0, 0.5, 0.1).unwrap();
+        assert_eq!(config.gain.kp, 1.0);
+        assert_eq!(
+            Config::try_new(1.0, f32::NAN, 0.1).unwrap_err(),
+            ConfigError::NonFiniteGain
+        );
+
+        let config = config.try_with_limits(-1.0, 1.0).unwrap();
+        assert_eq!((config.min, config.max), (-1.0, 1.0));
+        let config = config.try_with_limits(0.0, f32::INFINITY).unwrap();
+        assert_eq!((config.min, config.max), (0.0, f32::INFINITY));
+        assert_eq!(
+            config.try_with_limits(1.0, -1.0).unwrap_err(),
+            ConfigError::InvertedLimits
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "serde")]
+    fn test_config_serde_round_trip() {
+        let config: Config = Config::new(1.0, 0.5, 0.25)
+            .with_limits(-1.0, 1.0)
+            .with_anti_windup(AntiWindup::BackCalculation { kt: 2.0 })
+            .with_derivative_filter(DerivativeFilter::Divisor(10.0))
+            .with_rate_limits(4.0, f32::INFINITY)
+            .with_output_deadband(OutputDeadband::Nearest(0.1));
+
+        let json = serde_json::to_string(&config).unwrap();
+        let parsed: Config = serde_json::from_str(&json).unwrap();
+        assert_eq!(parsed.gain.kp, 1.0);
+        assert_eq!((parsed.min, parsed.max), (-1.0, 1.0));
+        assert_eq!(parsed.anti_windup, AntiWindup::BackCalculation { kt: 2.0 });
+        assert_eq!(parsed.d_filter, DerivativeFilter::Divisor(10.0));
+        assert_eq!((parsed.rise_rate, parsed.fall_rate), (4.0, f32::INFINITY));
+        assert_eq!(parsed.output_deadband, OutputDeadband::Nearest(0.1));
+        assert_eq!(
+            (parsed.i_min, parsed.i_max),
+            (f32::NEG_INFINITY, f32::INFINITY)
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "serde")]
+    fn test_config_serde_infinite_limits() {
+        let json = serde_json::to_value(Config::<f64>::default()).unwrap();
+        assert!(json["min"].is_null());
+        assert!(json["max"].is_null());
+        assert!(json["rise_rate"].is_null());
+
+        // Missing fields take their default values.
+        let json = r#"{ "gain": { "kp": 2.0, "ki": 1.0, "kd": 0.0 }, "max": 10.0, "min": null }"#;
+        let config: Config<f64> = serde_json::from_str(json).unwrap();
+        assert_eq!(config.gain.kp, 2.0);
+        assert_eq!((config.min, config.max), (f64::NEG_INFINITY, 10.0));
+        assert_eq!(config.fall_rate, f64::INFINITY);
+        assert!(config.freeze_integral);
     }
 }
@@ -0,0 +1,321 @@
+//! The `fixed_rate` module provides a PID controller for a constant sample period.
+//!
+//! [`FixedRatePid`] computes the same control law as [`VelPid`](crate::VelPid) updated with a constant `dt`,
+//! but the discrete coefficients are computed once from a [`Config`] and the period,
+//! so each update takes a few multiplications and no division.
+//! Without a derivative filter, the output follows the difference equation
+//!
+//! `u[k] = u[k-1] + q0 e[k] + q1 e[k-1] + q2 e[k-2]`
+//!
+//! With a derivative filter, the derivative part of the increment is filtered by a first-order recursion
+//! (see [`Coefficients`]).
+//!
+//! The output limits, integrator limits, rate limits, output deadband, discretisation methods and feedforward of [`Config`] are supported.
+//! As in `VelPid`, the output is the integrator, so the integrator limits clamp the output in addition to the output limits.
+//! The error deadband is not supported, and the velocity form needs no anti-windup strategy.
+//!
+//! # Examples
+//!
+//! ```rust
+//! use advanced_pid::{fixed_rate::FixedRatePid, PidConfig};
+//!
+//! let config = PidConfig::new(1.0, 0.3, 0.1).with_limits(-1.0, 1.0);
+//! // A 20 kHz loop.
+//! let mut pid = FixedRatePid::new(config, 50e-6);
+//!
+//! let target = 1.0;
+//! let actual = 0.0;
+//!
+//! println!("{}", pid.update(target, actual));
+//! ```
+use super::config::{Config, ConfigError};
+use super::Float;
+use super::Mode;
+
+/// `Coefficients` are the discrete coefficients of a [`FixedRatePid`].
+///
+/// The output increment is `q0 e[k] + q1 e[k-1] + q2 e[k-2]` without a derivative filter (`r` is zero).
+/// With a derivative filter, the derivative part `q2 (e[k] - 2 e[k-1] + e[k-2])` of the increment is replaced by
+/// `d[k] = r d[k-1] + q2 (e[k] - 2 e[k-1] + e[k-2])`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Coefficients<F = f32> {
+    /// Coefficient of the current error.
+    pub q0: F,
+    /// Coefficient of the previous error.
+    pub q1: F,
+    /// Coefficient of the error before the previous one.
+    pub q2: F,
+    /// Pole of the derivative filter.
+    pub r: F,
+}
+
+/// `FixedRatePid` is a velocity form PID controller with a constant sample period.
+#[derive(Debug, Clone)]
+pub struct FixedRatePid<F = f32> {
+    config: Config<F>,
+    period: F,
+    // The proportional and integral parts of `q0` and `q1`.
+    p0: F,
+    p1: F,
+    // `p0` on the first update, when there is no previous error to integrate.
+    p_first: F,
+    qd: F,
+    r: F,
+    fall_step: F,
+    rise_step: F,
+    output: F,
+    pre_error: F,
+    pre_pre_error: F,
+    d_term: F,
+    pre_feedforward: F,
+    mode: Mode<F>,
+}
+
+impl<F: Float> FixedRatePid<F> {
+    /// Creates a new `FixedRatePid` with the specified configuration and sample period.
+    pub fn new(config: Config<F>, period: F) -> Self {
+        let mut pid = Self {
+            config: Config::default(),
+            period,
+            p0: F::ZERO,
+            p1: F::ZERO,
+            p_first: F::ZERO,
+            qd: F::ZERO,
+            r: F::ZERO,
+            fall_step: F::INFINITY,
+            rise_step: F::INFINITY,
+            output: F::ZERO,
+            pre_error: F::ZERO,
+            pre_pre_error: F::NAN,
+            d_term: F::ZERO,
+            pre_feedforward: F::ZERO,
+            mode: Mode::Automatic,
+        };
+        pid.set_config(config);
+        pid
+    }
+
+    /// Creates a new `FixedRatePid` after validating the configuration and the sample period.
+    ///
+    /// # Errors
+    ///
+    /// Returns the [`ConfigError`] found by [`Config::validate()`],
+    /// or [`ConfigError::InvalidParameter`] if the period is not positive and finite.
+    pub fn try_new(config: Config<F>, period: F) -> Result<Self, ConfigError> {
+        config.validate()?;
+        if !period.is_finite() || period <= F::ZERO {
+            return Err(ConfigError::InvalidParameter);
+        }
+        Ok(Self::new(config, period))
+    }
+
+    /// Updates the controller with the specified set point and actual value.
+    /// Returns the controller output.
+    pub fn update(&mut self, set_point: F, actual: F) -> F {
+        self.update_with_feedforward(set_point, actual, F::ZERO)
+    }
+
+    /// Updates the controller with the specified set point, actual value, and feedforward value.
+    /// Returns the controller output.
+    pub fn update_with_feedforward(&mut self, set_point: F, actual: F, feedforward: F) -> F {
+        let error = set_point - actual;
+        let du = if self.pre_pre_error.is_nan() {
+            self.p_first * error
+        } else {
+            let second_difference = error - (self.pre_error + self.pre_error) + self.pre_pre_error;
+            self.d_term = self.r * self.d_term + self.qd * second_difference;
+            self.p0 * error + self.p1 * self.pre_error + self.d_term
+        };
+        self.pre_pre_error = self.pre_error;
+        self.pre_error = error;
+        // The rate limits are skipped after a non-finite output, which would turn the limits into NaN.
+        let (lower, upper) = if self.output.is_finite() {
+            (self.output - self.fall_step, self.output + self.rise_step)
+        } else {
+            (F::NEG_INFINITY, F::INFINITY)
+        };
+        let (min, max) = (
+            self.config.min.clamp(lower, upper),
+            self.config.max.clamp(lower, upper),
+        );
+        self.output = match self.mode {
+            // The feedforward value is absolute, so only its change is added.
+            Mode::Automatic => self.output + du + (feedforward - self.pre_feedforward),
+            // Following the manual output makes the next increment start from it.
+            Mode::Manual(output) => output,
+        }
+        .clamp(self.config.i_min, self.config.i_max)
+        .clamp(min, max);
+        self.pre_feedforward = feedforward;
+        self.config.output_deadband.apply(self.output)
+    }
+
+    /// Returns the discrete coefficients.
+    pub fn coefficients(&self) -> Coefficients<F> {
+        let two = F::from_f64(2.0);
+        Coefficients {
+            q0: self.p0 + self.qd,
+            q1: self.p1 - two * self.qd,
+            q2: self.qd,
+            r: self.r,
+        }
+    }
+
+    /// Returns the sample period.
+    pub fn period(&self) -> F {
+        self.period
+    }
+
+    /// Returns a reference to the configuration.
+    pub fn config(&self) -> &Config<F> {
+        &self.config
+    }
+
+    /// Sets the configuration and recomputes the coefficients, keeping the controller's state.
+    pub fn set_config(&mut self, config: Config<F>) {
+        let h = self.period;
+        let (kp, ki, kd) = (config.gain.kp, config.gain.ki, config.gain.kd);
+        // The filter and the integral are linear, so their coefficients are their responses to unit inputs.
+        let (mut decaying, mut rising) = (F::ONE, F::ZERO);
+        let r = config.filter_derivative(&mut decaying, F::ZERO, h);
+        let d_gain = config.filter_derivative(&mut rising, F::ONE, h);
+        self.p0 = kp + ki * h * config.integral_error(F::ONE, F::ZERO);
+        self.p1 = -kp + ki * h * config.integral_error(F::ZERO, F::ONE);
+        self.p_first = kp + ki * h;
+        self.qd = kd * d_gain / h;
+        self.r = r;
+        self.fall_step = config.fall_rate * h;
+        self.rise_step = config.rise_rate * h;
+        self.output = self.output.clamp(config.i_min, config.i_max);
+        self.config = config;
+    }
+
+    /// Sets the mode to manual with the specified output.
+    pub fn set_manual(&mut self, output: F) {
+        self.mode = Mode::Manual(output);
+    }
+
+    /// Sets the mode to automatic. The output continues from the last output.
+    pub fn set_auto(&mut self) {
+        self.mode = Mode::Automatic;
+    }
+
+    /// Returns the current mode.
+    pub fn mode(&self) -> Mode<F> {
+        self.mode
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::{DerivativeFilter, Discretization};
+    use crate::{Pid, PidController, VelPid};
+
+    fn errors() -> impl Iterator<Item = f64> {
+        (0..40).map(|k| if k < 20 { 0.1 * k as f64 } else { 1.0 })
+    }
+
+    #[test]
+    fn test_fixed_rate_pid_coefficients() {
+        let pid: FixedRatePid = FixedRatePid::new(Config::new(2.0, 4.0, 0.5), 0.25);
+        assert_eq!(
+            pid.coefficients(),
+            Coefficients {
+                q0: 5.0,
+                q1: -6.0,
+                q2: 2.0,
+                r: 0.0
+            }
+        );
+    }
+
+    #[test]
+    fn test_fixed_rate_pid_matches_vel_pid() {
+        let dt = 0.01;
+        for discretization in [
+            Discretization::ForwardEuler,
+            Discretization::BackwardEuler,
+            Discretization::Tustin,
+            Discretization::TustinPrewarped,
+        ] {
+            let config = Config::new(1.5, 3.0, 0.05)
+                .with_limits(-2.0, 1.2)
+                .with_integral_limits(-1.5, 0.3)
+                .with_rate_limits(40.0, 60.0)
+                .with_derivative_filter(DerivativeFilter::TimeConstant(0.02))
+                .with_discretization(discretization);
+            let mut vel_pid: VelPid<f64> = VelPid::new(config.clone());
+            let mut fixed_rate = FixedRatePid::new(config, dt);
+            let mut peak: f64 = 0.0;
+            for (k, actual) in errors().enumerate() {
+                let feedforward = if k < 30 { 0.0 } else { 0.3 };
+                let expected = vel_pid.update_with_feedforward(1.0, actual, feedforward, dt);
+                let output = fixed_rate.update_with_feedforward(1.0, actual, feedforward);
+                peak = peak.max(output);
+                assert!(
+                    (output - expected).abs() < 1e-9,
+                    "{:?} at {}: {} != {}",
+                    discretization,
+                    k,
+                    output,
+                    expected
+                );
+            }
+            // The integrator limits bound the output below the output limits.
+            assert_eq!(peak, 0.3, "{:?}", discretization);
+        }
+    }
+
+    #[test]
+    fn test_fixed_rate_pid_matches_pid() {
+        // The positional and velocity forms agree when the first error is zero and the output is not limited.
+        let dt = 0.05;
+        let config = Config::new(0.8, 2.0, 0.1);
+        let mut pid: Pid<f64> = Pid::new(config.clone());
+        let mut fixed_rate = FixedRatePid::new(config, dt);
+        for actual in errors().map(|error| 0.5 - error) {
+            let expected = pid.update(0.5, actual, dt);
+            let output = fixed_rate.update(0.5, actual);
+            assert!(
+                (output - expected).abs() < 1e-9,
+                "{} != {}",
+                output,
+                expected
+            );
+        }
+    }
+
+    #[test]
+    fn test_fixed_rate_pid_bumpless_transfer() {
+        let mut pid: FixedRatePid = FixedRatePid::new(Config::new(0.0, 1.0, 0.0), 0.5);
+
+        pid.set_manual(0.25);
+        assert_eq!(pid.update(1.0, 0.0), 0.25);
+        pid.set_auto();
+        assert_eq!(pid.update(1.0, 0.0), 0.75);
+    }
+
+    #[test]
+    fn test_fixed_rate_pid_non_finite() {
+        let mut pid: FixedRatePid = FixedRatePid::new(Config::new(1.0, 0.5, 0.0), 0.01);
+
+        assert!(pid.update(1.0, f32::NAN).is_nan());
+        assert!(pid.update(1.0, 0.0).is_nan());
+        pid.set_manual(0.5);
+        assert_eq!(pid.update(1.0, 0.0), 0.5);
+    }
+
+    #[test]
+    fn test_fixed_rate_pid_try_new() {
+        assert!(FixedRatePid::try_new(Config::new(1.0, 0.5, 0.1), 0.001).is_ok());
+        assert_eq!(
+            FixedRatePid::try_new(Config::new(1.0, 0.5, 0.1), 0.0).unwrap_err(),
+            ConfigError::InvalidParameter
+        );
+        assert_eq!(
+            FixedRatePid::try_new(Config::new(1.0, -0.5, 0.1), 0.001).unwrap_err(),
+            ConfigError::NegativeGain
+        );
+    }
+}
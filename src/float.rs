@@ -0,0 +1,180 @@
+//! The `float` module provides the [`Float`] trait for the numeric types used by the controllers.
+//!
+//! [`Float`] is implemented for `f32` and `f64`, so controllers of both precisions can be used in the same program.
+//!
+//! # Examples
+//!
+//! ```rust
+//! use advanced_pid::{prelude::*, Pid, PidConfig};
+//!
+//! let mut pid32: Pid<f32> = Pid::new(PidConfig::new(1.0, 0.3, 0.1));
+//! let mut pid64: Pid<f64> = Pid::new(PidConfig::new(1.0, 0.3, 0.1));
+//!
+//! println!("{}", pid32.update(1.0, 0.0, 1.0));
+//! println!("{}", pid64.update(1.0, 0.0, 1.0));
+//! ```
+use core::fmt::Debug;
+use core::ops::{Add, AddAssign, Div, DivAssign, Mul, MulAssign, Neg, Sub, SubAssign};
+
+/// `Float` is a trait for the floating point types used by the controllers.
+pub trait Float:
+    Copy
+    + Debug
+    + Default
+    + PartialOrd
+    + Add<Output = Self>
+    + Sub<Output = Self>
+    + Mul<Output = Self>
+    + Div<Output = Self>
+    + Neg<Output = Self>
+    + AddAssign
+    + SubAssign
+    + MulAssign
+    + DivAssign
+{
+    /// `0.0`
+    const ZERO: Self;
+    /// `1.0`
+    const ONE: Self;
+    /// Not a Number (NaN).
+    const NAN: Self;
+    /// Infinity (∞).
+    const INFINITY: Self;
+    /// Negative infinity (−∞).
+    const NEG_INFINITY: Self;
+
+    /// Converts an `f64` into `Self`, rounding if necessary.
+    fn from_f64(value: f64) -> Self;
+    /// Converts `self` into an `f64`.
+    fn to_f64(self) -> f64;
+    /// Returns `true` if `self` is NaN.
+    fn is_nan(self) -> bool;
+    /// Returns `true` if `self` is neither infinite nor NaN.
+    fn is_finite(self) -> bool;
+    /// Returns the absolute value of `self`.
+    fn abs(self) -> Self;
+    /// Returns the maximum of `self` and `other`, ignoring NaN.
+    fn max(self, other: Self) -> Self;
+    /// Returns the minimum of `self` and `other`, ignoring NaN.
+    fn min(self, other: Self) -> Self;
+    /// Restricts `self` to the interval `[min, max]`.
+    fn clamp(self, min: Self, max: Self) -> Self;
+}
+
+macro_rules! impl_float {
+    ($t:ident) => {
+        impl Float for $t {
+            const ZERO: Self = 0.0;
+            const ONE: Self = 1.0;
+            const NAN: Self = $t::NAN;
+            const INFINITY: Self = $t::INFINITY;
+            const NEG_INFINITY: Self = $t::NEG_INFINITY;
+
+            fn from_f64(value: f64) -> Self {
+                value as $t
+            }
+            fn to_f64(self) -> f64 {
+                self as f64
+            }
+            fn is_nan(self) -> bool {
+                $t::is_nan(self)
+            }
+            fn is_finite(self) -> bool {
+                $t::is_finite(self)
+            }
+            fn abs(self) -> Self {
+                $t::abs(self)
+            }
+            fn max(self, other: Self) -> Self {
+                $t::max(self, other)
+            }
+            fn min(self, other: Self) -> Self {
+                $t::min(self, other)
+            }
+            fn clamp(self, min: Self, max: Self) -> Self {
+                $t::clamp(self, min, max)
+            }
+        }
+    };
+}
+
+impl_float!(f32);
+impl_float!(f64);
+
+/// Returns the square root of `value`, or NaN if it is negative.
+///
+/// `core` does not provide `sqrt`, so it is computed with Newton's method in `f64`.
+pub(crate) fn sqrt<F: Float>(value: F) -> F {
+    let x = value.to_f64();
+    if x.is_nan() || x < 0.0 {
+        return F::NAN;
+    }
+    if x == 0.0 || x == f64::INFINITY {
+        return value;
+    }
+    // Halving the exponent gives an initial guess within a factor of two.
+    let mut root = f64::from_bits((x.to_bits() >> 1) + (1023 << 51));
+    for _ in 0..6 {
+        root = 0.5 * (root + x / root);
+    }
+    F::from_f64(root)
+}
+
+/// Returns the tangent of `angle`, in radians, which must be within `[0, π/2)`.
+///
+/// `core` does not provide `tan`, so it is computed from the Taylor series of the sine and cosine in `f64`,
+/// which are accurate to about 1e-13 within the range.
+pub(crate) fn tan<F: Float>(angle: F) -> F {
+    let x = angle.to_f64();
+    let x2 = x * x;
+    let (mut sin, mut cos) = (0.0, 0.0);
+    let (mut sin_term, mut cos_term) = (x, 1.0);
+    for n in 1..=10 {
+        sin += sin_term;
+        cos += cos_term;
+        let n = n as f64;
+        sin_term *= -x2 / ((2.0 * n) * (2.0 * n + 1.0));
+        cos_term *= -x2 / ((2.0 * n - 1.0) * (2.0 * n));
+    }
+    F::from_f64(sin / cos)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn halve<F: Float>(value: F) -> F {
+        value / F::from_f64(2.0)
+    }
+
+    #[test]
+    fn test_float_f32() {
+        assert_eq!(halve(3.0f32), 1.5);
+        assert_eq!(Float::clamp(3.0f32, -1.0, 1.0), 1.0);
+        assert!(<f32 as Float>::NAN.is_nan());
+    }
+
+    #[test]
+    fn test_float_f64() {
+        assert_eq!(halve(3.0f64), 1.5);
+        assert_eq!(Float::clamp(-3.0f64, -1.0, 1.0), -1.0);
+        assert_eq!(<f64 as Float>::ONE.to_f64(), 1.0);
+    }
+
+    #[test]
+    fn test_sqrt() {
+        assert_eq!(sqrt(4.0f32), 2.0);
+        assert_eq!(sqrt(0.0f64), 0.0);
+        assert!((sqrt(2.0f64) - core::f64::consts::SQRT_2).abs() < 1e-15);
+        assert!((sqrt(1e-12f64) - 1e-6).abs() < 1e-21);
+        assert!(sqrt(-1.0f32).is_nan());
+    }
+
+    #[test]
+    fn test_tan() {
+        assert_eq!(tan(0.0f64), 0.0);
+        assert!((tan(core::f64::consts::FRAC_PI_4) - 1.0).abs() < 1e-13);
+        assert!((tan(0.25f64) - 0.255_341_921_221_036_3).abs() < 1e-13);
+        assert!((tan(1.5f64) - 14.101_419_947_171_72).abs() < 1e-10);
+    }
+}
@@ -0,0 +1,240 @@
+//! The `gain_schedule` module provides a gain-scheduled controller.
+//!
+//! [`GainScheduled`] holds a table of breakpoints that map a scheduling variable to [`Gain`]s.
+//! On every update, it linearly interpolates the gains at the current scheduling variable and applies them to the wrapped controller
+//! with [`Configurable::set_gain()`], which keeps the controller's state so that changing gains do not make the output jump.
+//!
+//! # Examples
+//!
+//! ```rust
+//! use advanced_pid::{gain_schedule::GainScheduled, prelude::*, Pid, PidGain};
+//!
+//! let table = [
+//!     (0.0, PidGain { kp: 5.0, ki: 1.0, kd: 0.0 }),
+//!     (10.0, PidGain { kp: 1.0, ki: 0.2, kd: 0.0 }),
+//! ];
+//! let mut pid = GainScheduled::new(Pid::default(), table);
+//!
+//! let operating_point = 2.5;
+//! let target = 1.0;
+//! let actual = 0.0;
+//! let dt = 0.1;
+//!
+//! println!("{}", pid.update(operating_point, target, actual, dt));
+//! ```
+use super::config::{ConfigError, Gain};
+use super::Configurable;
+use super::Float;
+
+/// `GainScheduled` wraps a controller and schedules its gains from a table of breakpoints.
+///
+/// The breakpoints are `(scheduling variable, gain)` pairs in strictly increasing order of the scheduling variable.
+/// Outside the table, the gains of the nearest breakpoint are used.
+#[derive(Debug, Clone)]
+pub struct GainScheduled<C, const N: usize, F = f32> {
+    controller: C,
+    table: [(F, Gain<F>); N],
+}
+
+impl<C, const N: usize, F> GainScheduled<C, N, F>
+where
+    C: Configurable<F>,
+    F: Float,
+{
+    /// Creates a new `GainScheduled` with the specified controller and table of breakpoints.
+    ///
+    /// Use [`Self::try_new()`] to validate the table and the controller's configuration instead of panicking.
+    ///
+    /// # Panics
+    /// Panics if the table is empty or its scheduling variables are not strictly increasing.
+    pub fn new(controller: C, table: [(F, Gain<F>); N]) -> Self {
+        assert!(N > 0, "the gain table must not be empty");
+        assert!(
+            table.windows(2).all(|pair| pair[0].0 < pair[1].0),
+            "the scheduling variables must be strictly increasing"
+        );
+        Self { controller, table }
+    }
+
+    /// Creates a new `GainScheduled` after validating the table of breakpoints and the controller's configuration.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`ConfigError::InvalidParameter`] if the table is empty or its scheduling variables are not strictly increasing,
+    /// the [`ConfigError`] found by [`Gain::validate()`] in any of the scheduled gains,
+    /// or the one found by [`Config::validate()`](crate::config::Config::validate()) in the controller's configuration.
+    pub fn try_new(controller: C, table: [(F, Gain<F>); N]) -> Result<Self, ConfigError> {
+        if N == 0 || !table.windows(2).all(|pair| pair[0].0 < pair[1].0) {
+            return Err(ConfigError::InvalidParameter);
+        }
+        for (_, gain) in &table {
+            gain.validate()?;
+        }
+        controller.config().validate()?;
+        Ok(Self::new(controller, table))
+    }
+
+    /// Returns the gains interpolated at the specified scheduling variable.
+    pub fn gain_at(&self, scheduling_variable: F) -> Gain<F> {
+        let upper = self
+            .table
+            .iter()
+            .position(|(x, _)| scheduling_variable < *x)
+            .unwrap_or(N);
+        if upper == 0 {
+            return self.table[0].1.clone();
+        }
+        if upper == N {
+            return self.table[N - 1].1.clone();
+        }
+        let (x0, g0) = &self.table[upper - 1];
+        let (x1, g1) = &self.table[upper];
+        let t = (scheduling_variable - *x0) / (*x1 - *x0);
+        let lerp = |a: F, b: F| a + (b - a) * t;
+        Gain {
+            kp: lerp(g0.kp, g1.kp),
+            ki: lerp(g0.ki, g1.ki),
+            kd: lerp(g0.kd, g1.kd),
+        }
+    }
+
+    /// Updates the controller with the gains at the specified scheduling variable, and with the specified set point, actual value, and time delta.
+    /// Returns the controller output.
+    pub fn update(&mut self, scheduling_variable: F, set_point: F, actual: F, dt: F) -> F {
+        self.controller.set_gain(self.gain_at(scheduling_variable));
+        self.controller.update(set_point, actual, dt)
+    }
+
+    /// Returns the table of breakpoints.
+    pub fn table(&self) -> &[(F, Gain<F>); N] {
+        &self.table
+    }
+
+    /// Returns a reference to the wrapped controller.
+    pub fn controller(&self) -> &C {
+        &self.controller
+    }
+
+    /// Returns a mutable reference to the wrapped controller.
+    pub fn controller_mut(&mut self) -> &mut C {
+        &mut self.controller
+    }
+
+    /// Consumes the `GainScheduled` and returns the wrapped controller.
+    pub fn into_inner(self) -> C {
+        self.controller
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Pid, PidController, PidGain, VelPid};
+
+    fn table() -> [(f32, PidGain); 3] {
+        [
+            (
+                0.0,
+                PidGain {
+                    kp: 4.0,
+                    ki: 2.0,
+                    kd: 0.0,
+                },
+            ),
+            (
+                1.0,
+                PidGain {
+                    kp: 2.0,
+                    ki: 1.0,
+                    kd: 1.0,
+                },
+            ),
+            (
+                3.0,
+                PidGain {
+                    kp: 1.0,
+                    ki: 0.0,
+                    kd: 0.0,
+                },
+            ),
+        ]
+    }
+
+    #[test]
+    fn test_gain_scheduled_interpolation() {
+        let pid = GainScheduled::new(Pid::default(), table());
+
+        let gain = pid.gain_at(0.5);
+        assert_eq!((gain.kp, gain.ki, gain.kd), (3.0, 1.5, 0.5));
+        let gain = pid.gain_at(2.0);
+        assert_eq!((gain.kp, gain.ki, gain.kd), (1.5, 0.5, 0.5));
+        let gain = pid.gain_at(3.0);
+        assert_eq!((gain.kp, gain.ki, gain.kd), (1.0, 0.0, 0.0));
+        let gain = pid.gain_at(-1.0);
+        assert_eq!((gain.kp, gain.ki, gain.kd), (4.0, 2.0, 0.0));
+        let gain = pid.gain_at(5.0);
+        assert_eq!((gain.kp, gain.ki, gain.kd), (1.0, 0.0, 0.0));
+    }
+
+    #[test]
+    fn test_gain_scheduled_keeps_state() {
+        let mut pid = GainScheduled::new(Pid::default(), table());
+
+        assert_eq!(pid.update(0.0, 1.0, 0.0, 0.5), 5.0);
+        // The integral contribution of 1.0 is kept while the gains change.
+        assert_eq!(pid.update(3.0, 1.0, 1.0, 0.5), 1.0);
+        assert_eq!(pid.controller().config().gain.kp, 1.0);
+    }
+
+    #[test]
+    fn test_gain_scheduled_vel_pid() {
+        let mut pid = GainScheduled::new(VelPid::default(), table());
+
+        assert_eq!(pid.update(0.0, 1.0, 0.0, 0.5), 5.0);
+        assert_eq!(pid.update(3.0, 1.0, 1.0, 0.5), 4.0);
+    }
+
+    #[test]
+    fn test_gain_scheduled_try_new() {
+        assert!(GainScheduled::try_new(Pid::default(), table()).is_ok());
+
+        let mut unsorted = table();
+        unsorted.swap(0, 1);
+        assert_eq!(
+            GainScheduled::try_new(Pid::default(), unsorted).unwrap_err(),
+            ConfigError::InvalidParameter
+        );
+        let empty: [(f32, PidGain); 0] = [];
+        assert_eq!(
+            GainScheduled::try_new(Pid::default(), empty).unwrap_err(),
+            ConfigError::InvalidParameter
+        );
+
+        let mut negative = table();
+        negative[2].1.ki = -1.0;
+        assert_eq!(
+            GainScheduled::try_new(Pid::default(), negative).unwrap_err(),
+            ConfigError::NegativeGain
+        );
+        let mut non_finite = table();
+        non_finite[1].1.kd = f32::INFINITY;
+        assert_eq!(
+            GainScheduled::try_new(Pid::default(), non_finite).unwrap_err(),
+            ConfigError::NonFiniteGain
+        );
+
+        let inverted = Pid::new(crate::PidConfig::default().with_limits(1.0, -1.0));
+        assert_eq!(
+            GainScheduled::try_new(inverted, table()).unwrap_err(),
+            ConfigError::InvertedLimits
+        );
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_gain_scheduled_unsorted_table() {
+        let mut table = table();
+        table.swap(0, 1);
+        GainScheduled::new(Pid::default(), table);
+    }
+}
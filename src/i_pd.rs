@@ -16,50 +16,151 @@
 //!
 //! println!("{}", pid.update(target, actual, dt));
 //! ```
-use super::FloatType;
+use super::Configurable;
+use super::Float;
+use super::Integrator;
+use super::ManualMode;
+use super::Mode;
 use super::PidConfig;
 use super::PidController;
+use super::Terms;
 
 /// `Ipd` is a structure that implements the [`PidController`] trait.
 #[derive(Debug, Clone)]
-pub struct Ipd {
-    config: PidConfig,
-    i_term: FloatType,
-    pre_actual: FloatType,
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(
+    feature = "serde",
+    serde(bound(
+        serialize = "F: Float + serde::Serialize",
+        deserialize = "F: Float + serde::Deserialize<'de>"
+    ))
+)]
+pub struct Ipd<F = f32> {
+    config: PidConfig<F>,
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_float::nan"))]
+    i_term: F,
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_float::nan"))]
+    pre_i_error: F,
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_float::nan"))]
+    pre_actual: F,
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_float::nan"))]
+    d_term_lpf: F,
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_float::nan"))]
+    pre_output: F,
+    mode: Mode<F>,
+    #[cfg_attr(feature = "serde", serde(skip))]
+    terms: Option<Terms<F>>,
 }
 
-impl Default for Ipd {
+impl<F: Float> Default for Ipd<F> {
     /// Creates a new `Ipd` with the default configuration.
     fn default() -> Self {
         Self::new(PidConfig::default())
     }
 }
 
-impl PidController for Ipd {
+crate::state::impl_positional_snapshot!(Ipd, pre_actual);
+
+impl<F: Float> PidController<F> for Ipd<F> {
     /// Creates a new `Ipd` with the specified configuration.
-    fn new(config: PidConfig) -> Self {
+    fn new(config: PidConfig<F>) -> Self {
         Self {
             config,
-            i_term: 0.0,
-            pre_actual: FloatType::NAN,
+            i_term: F::ZERO,
+            pre_i_error: F::NAN,
+            pre_actual: F::NAN,
+            d_term_lpf: F::ZERO,
+            pre_output: F::ZERO,
+            mode: Mode::Automatic,
+            terms: None,
         }
     }
 
     /// Updates the `Ipd` controller with the specified set point, actual value, and time delta.
     /// Returns the controller output.
-    fn update(&mut self, set_point: FloatType, actual: FloatType, dt: FloatType) -> FloatType {
-        let error = set_point - actual;
-        self.i_term += error * dt;
+    fn update(&mut self, set_point: F, actual: F, dt: F) -> F {
+        self.update_with_feedforward(set_point, actual, F::ZERO, dt)
+    }
+
+    /// Updates the `Ipd` controller with the specified set point, actual value, feedforward value, and time delta.
+    /// Returns the controller output.
+    fn update_with_feedforward(&mut self, set_point: F, actual: F, feedforward: F, dt: F) -> F {
+        let (actual, error) = self.config.deadband(set_point, actual);
         let d_term = if self.pre_actual.is_nan() {
-            0.0
+            F::ZERO
         } else {
             (actual - self.pre_actual) / dt
         };
-        let output = self.config.gain.ki * self.i_term
-            - self.config.gain.kp * actual
-            - self.config.gain.kd * d_term;
+        let d_term = self
+            .config
+            .filter_derivative(&mut self.d_term_lpf, d_term, dt);
+        let p_term = -self.config.gain.kp * actual;
+        let d_term = -(self.config.gain.kd * d_term);
+        let others = p_term + d_term + feedforward;
+        let limits = self.config.output_limits(self.pre_output, dt);
+        let (output, unclamped) = match self.mode {
+            Mode::Automatic => self.config.integrate(
+                &mut self.i_term,
+                self.config.gain.ki * self.config.integral_error(error, self.pre_i_error) * dt,
+                others,
+                dt,
+                limits,
+            ),
+            Mode::Manual(output) => self.config.track(&mut self.i_term, output, others, limits),
+        };
+        self.pre_output = output;
+        self.pre_i_error = error;
         self.pre_actual = actual;
-        output.clamp(self.config.min, self.config.max)
+        let terms = Terms {
+            p: p_term,
+            i: self.i_term,
+            d: d_term,
+            feedforward,
+            unclamped,
+            output: self.config.output_deadband.apply(output),
+            saturated: output <= limits.0 || output >= limits.1,
+        };
+        self.terms = Some(terms);
+        terms.output
+    }
+
+    fn last_terms(&self) -> Option<Terms<F>> {
+        self.terms
+    }
+}
+
+impl<F: Float> Configurable<F> for Ipd<F> {
+    fn config(&self) -> &PidConfig<F> {
+        &self.config
+    }
+
+    fn set_config(&mut self, config: PidConfig<F>) {
+        self.i_term = self.i_term.clamp(config.i_min, config.i_max);
+        self.config = config;
+    }
+}
+
+impl<F: Float> ManualMode<F> for Ipd<F> {
+    fn set_manual(&mut self, output: F) {
+        self.mode = Mode::Manual(output);
+    }
+
+    fn set_auto(&mut self) {
+        self.mode = Mode::Automatic;
+    }
+
+    fn mode(&self) -> Mode<F> {
+        self.mode
+    }
+}
+
+impl<F: Float> Integrator<F> for Ipd<F> {
+    fn integral(&self) -> F {
+        self.i_term
+    }
+
+    fn set_integral(&mut self, integral: F) {
+        self.i_term = integral.clamp(self.config.i_min, self.config.i_max);
     }
 }
 
@@ -115,4 +216,85 @@ mod tests {
         let output = pid.update(1.0, 1.0, 1.0);
         assert_eq!(output, -1.0);
     }
+
+    #[test]
+    fn test_i_pd_controller_terms() {
+        let mut pid = Ipd::new(PidConfig::new(1.0, 0.0, 1.0));
+
+        pid.update(1.0, 0.5, 1.0);
+        let terms = pid.update_detailed(1.0, 1.0, 0.0, 1.0).unwrap();
+        assert_eq!((terms.p, terms.i, terms.d), (-1.0, 0.0, -0.5));
+        assert_eq!(terms.output, -1.5);
+    }
+
+    #[test]
+    fn test_i_pd_controller_integrator() {
+        let mut pid = Ipd::new(PidConfig::new(0.0, 1.0, 0.0).with_integral_limits(0.0, 1.0));
+
+        pid.set_integral(-0.5);
+        assert_eq!(pid.integral(), 0.0);
+        pid.set_integral(0.5);
+        assert_eq!(pid.update(1.0, 1.0, 1.0), 0.5);
+        pid.reset_integral();
+        assert_eq!(pid.update(1.0, 1.0, 1.0), 0.0);
+    }
+
+    #[test]
+    fn test_i_pd_controller_tustin() {
+        let config = PidConfig::new(0.0, 1.0, 0.0)
+            .with_discretization(crate::config::Discretization::Tustin);
+        let mut pid = Ipd::new(config);
+
+        assert_eq!(pid.update(0.0, 0.0, 1.0), 0.0);
+        assert_eq!(pid.update(1.0, 0.0, 1.0), 0.5);
+        assert_eq!(pid.update(2.0, 0.0, 1.0), 2.0);
+    }
+
+    #[test]
+    fn test_i_pd_controller_conditional_integration() {
+        let config = PidConfig::new(0.0, 1.0, 0.0)
+            .with_limits(-1.0, 1.0)
+            .with_anti_windup(crate::config::AntiWindup::Conditional);
+        let mut pid = Ipd::new(config);
+
+        assert_eq!(pid.update(1.0, 0.0, 1.0), 1.0);
+        assert_eq!(pid.update(1.0, 0.0, 1.0), 1.0);
+        assert_eq!(pid.update(1.0, 0.0, 1.0), 1.0);
+        assert_eq!(pid.update(-1.0, 0.0, 1.0), 0.0);
+    }
+
+    #[test]
+    fn test_i_pd_controller_back_calculation() {
+        let config = PidConfig::new(0.0, 1.0, 0.0)
+            .with_limits(-1.0, 1.0)
+            .with_anti_windup(crate::config::AntiWindup::BackCalculation { kt: 1.0 });
+        let mut pid = Ipd::new(config);
+
+        assert_eq!(pid.update(1.0, 0.0, 1.0), 1.0);
+        assert_eq!(pid.update(1.0, 0.0, 1.0), 1.0);
+        assert_eq!(pid.update(-1.0, 0.0, 1.0), 0.0);
+    }
+
+    #[test]
+    fn test_i_pd_controller_bumpless_transfer() {
+        let mut pid = Ipd::new(PidConfig::new(2.0, 1.0, 0.5));
+
+        pid.set_manual(0.4);
+        assert_eq!(pid.update(1.0, 0.3, 0.1), 0.4);
+        assert_eq!(pid.update(1.0, 0.3, 0.1), 0.4);
+        pid.set_auto();
+        let output = pid.update(1.0, 0.3, 0.1);
+        assert!((output - 0.47).abs() < 1e-6, "output: {}", output);
+    }
+
+    #[test]
+    fn test_i_pd_controller_error_deadband() {
+        let config = PidConfig::new(1.0, 0.0, 1.0).with_error_deadband(0.1, true);
+        let mut pid = Ipd::new(config);
+
+        let output = pid.update(1.0, 1.0, 1.0);
+        assert_eq!(output, -1.0);
+        assert_eq!(pid.update(1.0, 1.05, 1.0), output);
+        assert_eq!(pid.update(1.0, 0.95, 1.0), output);
+    }
 }
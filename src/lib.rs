@@ -9,12 +9,36 @@
 //! - Velocity form PID Control ([`vel_pid`] module)
 //! - PI-D Control where the Derivative action is based on the Process Variable (PV) ([`pi_d`] module)
 //! - I-PD Control where both Proportional and Derivative actions are based on the Process Variable (PV) ([`i_pd`] module)
-//! - Customizable PID gains and limits ([`config`] module)
+//! - Two-degree-of-freedom PID Control with setpoint weights ([`two_dof_pid`] module)
+//! - Fixed-point PID Controls for microcontrollers without an FPU ([`fixed`] module)
+//! - Fixed-rate PID Control with precomputed difference-equation coefficients ([`fixed_rate`] module)
+//! - Cascade control of nested controllers ([`cascade`] module)
+//! - Timestamp-driven updates from `Instant`s or wrapping tick counters ([`clock`] module)
+//! - Gain scheduling with interpolated gain tables ([`gain_schedule`] module)
+//! - Relay-feedback autotuning of the ultimate gain and period ([`autotune`] module)
+//! - Classic tuning rules for P, PI and PID control from process models or ultimate gains ([`tuning`] module)
+//! - Process models and a closed-loop runner for simulations ([`plant`] module)
+//! - Step-response performance metrics ([`metrics`] module)
+//! - Plain-data snapshots of the controllers' state, to restore them after a restart or a failover ([`state`] module, [`Snapshot`] trait)
+//! - Customizable PID gains and limits, with validation ([`config`] module, [`config::ConfigError`])
+//! - Standard (ISA) and series gain forms with conversions ([`config::StandardGain`], [`config::SeriesGain`])
+//! - Anti-windup strategies for the positional controllers ([`config::AntiWindup`])
+//! - Derivative low-pass filter for every controller ([`config::DerivativeFilter`])
+//! - Forward Euler, backward Euler and Tustin discretisation, with prewarping for the derivative filter ([`config::Discretization`])
+//! - Output rate limits for every controller ([`config::Config::with_rate_limits()`])
+//! - Error and output deadbands for every controller ([`config::Config::with_error_deadband()`], [`config::OutputDeadband`])
+//! - Feedforward input for every controller ([`PidController::update_with_feedforward()`])
+//! - P, I, D and feedforward contributions of the last update for every controller ([`PidController::last_terms()`])
+//! - Preloading, resetting and limiting the integrator through the [`Integrator`] trait
 //!
 //! ## Usage
 //! To use, implement the [`PidController`] trait for your controller.
-//! The trait provides a `new` method for creating a new controller, an `update` method for updating the controller,
-//! and a `reset_config` method for resetting the controller's configuration.
+//! The trait provides a `new` method for creating a new controller, a checked `try_new` counterpart, an `update` method for updating the controller,
+//! a `reset_config` method for resetting the controller's configuration,
+//! and an `update_with_feedforward` method for adding a feedforward value.
+//! The [`Configurable`] trait provides `set_config`/`set_gain` methods for changing the configuration at runtime without losing the controller's state,
+//! the [`ManualMode`] trait provides `set_manual`/`set_auto` methods for switching between manual and automatic [`Mode`] with bumpless transfer,
+//! and the [`Integrator`] trait gives generic access to the integrator of the controllers.
 //!
 //! ## Installation
 //! To install, run the following Cargo command in your project directory:
@@ -25,7 +49,7 @@
 //! Or add the following to your Cargo.toml:
 //! ```toml
 //! [dependencies]
-//! advanced-pid = "0.2.3"
+//! advanced-pid = "0.3.0"
 //! ```
 //!
 //! ## No-std support
@@ -33,16 +57,27 @@
 //! To use this library in a `no_std` environment, disable the default features in your `Cargo.toml`:
 //! ```toml
 //! [dependencies]
-//! advanced-pid = { version = "0.2.3", default-features = false }
+//! advanced-pid = { version = "0.3.0", default-features = false }
 //! ```
 //!
-//! ## Floating point precision
-//! This library allows switching between `f32` and `f64` floating point types through feature flags.
-//! By default, `f32` precision is used.
-//! To use `f64` precision, enable the `f64` feature in your `Cargo.toml`:
+//! ## Serde support
+//! The `serde` feature derives `Serialize` and `Deserialize` for the [`config`] types and the state of the controllers,
+//! so that configurations can be loaded from JSON or TOML files and controllers can be saved and restored.
+//! Unbounded limits and rates are represented as `null` (omitted in TOML), and missing configuration fields take their default values:
 //! ```toml
 //! [dependencies]
-//! advanced-pid = { version = "0.2.3", features = ["f64"] }
+//! advanced-pid = { version = "0.3.0", features = ["serde"] }
+//! ```
+//!
+//! ## Floating point precision
+//! The controllers and their configuration are generic over the [`Float`] trait, which is implemented for `f32` and `f64`.
+//! By default, `f32` precision is used.
+//! Both precisions can be used in the same program:
+//! ```
+//! use advanced_pid::{prelude::*, Pid, PidConfig};
+//!
+//! let mut mcu: Pid = Pid::new(PidConfig::new(1.0, 0.3, 0.1));
+//! let mut host: Pid<f64> = Pid::new(PidConfig::new(1.0, 0.3, 0.1));
 //! ```
 //!
 //! ## Examples
@@ -70,33 +105,103 @@
 #[macro_use]
 extern crate std;
 
-#[cfg(not(feature = "f64"))]
-type FloatType = f32;
-#[cfg(feature = "f64")]
-type FloatType = f64;
-
 pub mod prelude;
+#[cfg(feature = "serde")]
+mod serde_float;
 
+pub mod autotune;
+pub mod cascade;
+pub mod clock;
 pub mod config;
+pub mod fixed;
+pub mod fixed_rate;
+pub mod float;
+pub mod gain_schedule;
 pub mod i_pd;
+pub mod metrics;
 pub mod pi_d;
 pub mod pid;
+pub mod plant;
+pub mod state;
+pub mod tuning;
+pub mod two_dof_pid;
 pub mod vel_pid;
 
 /// Type alias for [PID gains](config::Gain).
-pub type PidGain = config::Gain;
+pub type PidGain<F = f32> = config::Gain<F>;
 /// Type alias for [PID configuration](config::Config).
-pub type PidConfig = config::Config;
+pub type PidConfig<F = f32> = config::Config<F>;
 
+pub use crate::float::Float;
 pub use crate::i_pd::Ipd;
 pub use crate::pi_d::PiD;
 pub use crate::pid::Pid;
+pub use crate::two_dof_pid::TwoDofPid;
 pub use crate::vel_pid::VelPid;
 
+/// `Mode` is the operating mode of a controller.
+///
+/// See also: [`ManualMode::set_manual()`], [`ManualMode::set_auto()`]
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Mode<F = f32> {
+    /// The controller computes its output from the set point and the actual value.
+    #[default]
+    Automatic,
+    /// The controller outputs the specified value, clamped to the output limits.
+    /// Its internal state tracks this output on every update, so that switching back to automatic is bumpless.
+    Manual(F),
+}
+
+/// `Terms` are the contributions to a controller's output in its last update.
+///
+/// For the positional controllers, `p`, `i` and `d` are the proportional, integral and derivative contributions to the output.
+/// For [`VelPid`], they are the increments added to the previous output, and `feedforward` is the change of the feedforward value.
+///
+/// See also: [`PidController::last_terms()`]
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct Terms<F = f32> {
+    /// Proportional contribution.
+    pub p: F,
+    /// Integral contribution, after the anti-windup strategy.
+    pub i: F,
+    /// Derivative contribution, after the derivative filter.
+    pub d: F,
+    /// Feedforward contribution.
+    pub feedforward: F,
+    /// Output before the output limits and rate limits, and before the anti-windup strategy adjusts the integral.
+    pub unclamped: F,
+    /// Output returned by the update, after the limits and the output deadband.
+    pub output: F,
+    /// `true` if the output is at its limits or rate limits, or for `VelPid`, at its integrator limits.
+    pub saturated: bool,
+}
+
 /// `PidController` is a trait that provides a standard interface for PID controllers.
 ///
 /// It provides methods for creating a new controller [`Self::new()`], updating the controller [`Self::update()`], and resetting the controller's configuration [`Self::reset_config()`].
-pub trait PidController {
+/// Implementors provide [`Self::new()`] and [`Self::update()`]; the other methods have default implementations.
+/// The [`Configurable`], [`ManualMode`] and [`Integrator`] traits extend it for controllers that support them.
+/// It is generic over the [`Float`] type of the controller, which defaults to `f32`.
+/// ```
+/// use advanced_pid::{prelude::*, PidConfig};
+///
+/// struct Proportional(f32);
+///
+/// impl PidController for Proportional {
+///     fn new(config: PidConfig) -> Self {
+///         Self(config.gain.kp)
+///     }
+///
+///     fn update(&mut self, set_point: f32, actual: f32, _dt: f32) -> f32 {
+///         self.0 * (set_point - actual)
+///     }
+/// }
+///
+/// let mut controller = Proportional::new(PidConfig::new(2.0, 0.0, 0.0));
+/// assert_eq!(controller.update_with_feedforward(1.0, 0.5, 0.25, 0.1), 1.25);
+/// ```
+pub trait PidController<F: Float = f32> {
     /// Creates a new controller with the specified configuration.
     /// ```
     /// use advanced_pid::{prelude::*, Pid, PidConfig};
@@ -104,19 +209,81 @@ pub trait PidController {
     /// let config = PidConfig::new(1.0, 0.3, 0.1);
     /// let controller = Pid::new(config);
     /// ```
-    fn new(config: PidConfig) -> Self;
+    fn new(config: PidConfig<F>) -> Self;
+
+    /// Creates a new controller with the specified configuration, if it is [valid](config::Config::validate()).
+    /// ```
+    /// use advanced_pid::{config::ConfigError, prelude::*, Pid, PidConfig};
+    ///
+    /// let config = PidConfig::new(1.0, 0.3, 0.1).with_limits(1.0, -1.0);
+    /// assert_eq!(Pid::try_new(config).unwrap_err(), ConfigError::InvertedLimits);
+    /// ```
+    fn try_new(config: PidConfig<F>) -> Result<Self, config::ConfigError>
+    where
+        Self: core::marker::Sized,
+    {
+        config.validate()?;
+        Ok(Self::new(config))
+    }
 
     /// Updates the controller with the specified set point, actual value, and time delta.
     /// Returns the controller output.
     /// ```
     /// use advanced_pid::{prelude::*, Pid};
     ///
-    /// let mut controller = Pid::default();
+    /// let mut controller: Pid = Pid::default();
     /// let output = controller.update(1.0, 0.0, 0.1);
     /// ```
-    fn update(&mut self, set_point: FloatType, actual: FloatType, dt: FloatType) -> FloatType;
+    fn update(&mut self, set_point: F, actual: F, dt: F) -> F;
+
+    /// Updates the controller with the specified set point, actual value, feedforward value, and time delta.
+    /// Returns the controller output.
+    ///
+    /// The default implementation adds the feedforward value to the output of [`Self::update()`].
+    /// The controllers of this crate add it before limiting instead, so the limits and the anti-windup strategy take it into account.
+    /// ```
+    /// use advanced_pid::{prelude::*, Pid, PidConfig};
+    ///
+    /// let mut controller = Pid::new(PidConfig::new(1.0, 0.0, 0.0).with_limits(-1.0, 1.0));
+    /// let output = controller.update_with_feedforward(1.0, 0.5, 0.8, 0.1);
+    /// assert_eq!(output, 1.0);
+    /// ```
+    fn update_with_feedforward(&mut self, set_point: F, actual: F, feedforward: F, dt: F) -> F {
+        self.update(set_point, actual, dt) + feedforward
+    }
+
+    /// Returns the contributions to the output in the last update, or `None` before the first update.
+    ///
+    /// The default implementation returns `None`, for controllers that do not record their terms.
+    /// ```
+    /// use advanced_pid::{prelude::*, Pid, PidConfig};
+    ///
+    /// let mut controller = Pid::new(PidConfig::new(1.0, 0.5, 0.0).with_limits(-1.0, 1.0));
+    /// controller.update(1.0, 0.0, 1.0);
+    /// let terms = controller.last_terms().unwrap();
+    /// assert_eq!((terms.p, terms.i, terms.unclamped, terms.output), (1.0, 0.5, 1.5, 1.0));
+    /// assert!(terms.saturated);
+    /// ```
+    fn last_terms(&self) -> Option<Terms<F>> {
+        None
+    }
+
+    /// Updates the controller like [`Self::update_with_feedforward()`], and returns the contributions to the output.
+    /// Returns `None` if the controller does not record them (see [`Self::last_terms()`]).
+    fn update_detailed(
+        &mut self,
+        set_point: F,
+        actual: F,
+        feedforward: F,
+        dt: F,
+    ) -> Option<Terms<F>> {
+        self.update_with_feedforward(set_point, actual, feedforward, dt);
+        self.last_terms()
+    }
 
     /// Resets the controller's configuration to the specified configuration.
+    /// The controller's state is discarded as well, as if it was created with [`Self::new()`].
+    /// Use [`Configurable::set_config()`] to keep the state.
     /// ```
     /// use advanced_pid::{prelude::*, Pid, PidConfig};
     ///
@@ -124,10 +291,177 @@ pub trait PidController {
     /// let config = PidConfig::new(1.0, 0.3, 0.1);
     /// controller.reset_config(config);
     /// ```
-    fn reset_config(&mut self, config: PidConfig)
+    fn reset_config(&mut self, config: PidConfig<F>)
     where
         Self: core::marker::Sized,
     {
         *self = Self::new(config);
     }
 }
+
+/// `Configurable` is a trait that gives access to the configuration of a controller while it is running.
+///
+/// It allows changing the configuration or the gains without losing the controller's state,
+/// unlike [`PidController::reset_config()`].
+pub trait Configurable<F: Float = f32>: PidController<F> {
+    /// Returns the controller's configuration.
+    fn config(&self) -> &PidConfig<F>;
+
+    /// Changes the controller's configuration while keeping its state.
+    ///
+    /// The integral term is held as its contribution to the output, so changing `ki` does not make the output jump.
+    /// It is clamped to the new integrator limits.
+    /// ```
+    /// use advanced_pid::{prelude::*, Pid, PidConfig};
+    ///
+    /// let mut controller = Pid::new(PidConfig::new(0.0, 1.0, 0.0));
+    /// assert_eq!(controller.update(1.0, 0.0, 1.0), 1.0);
+    /// controller.set_config(PidConfig::new(0.0, 2.0, 0.0));
+    /// assert_eq!(controller.update(1.0, 1.0, 1.0), 1.0);
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// Panics if the integrator limits are NaN or inverted, and the next update panics if the output limits are.
+    /// Use [`Self::try_set_config()`] to validate the configuration first.
+    fn set_config(&mut self, config: PidConfig<F>);
+
+    /// Changes the controller's configuration while keeping its state, after validating it.
+    ///
+    /// # Errors
+    ///
+    /// Returns the [`ConfigError`](config::ConfigError) found by [`PidConfig::validate()`],
+    /// in which case the configuration is left unchanged.
+    /// ```
+    /// use advanced_pid::{config::ConfigError, prelude::*, Pid, PidConfig};
+    ///
+    /// let mut controller = Pid::new(PidConfig::new(1.0, 0.3, 0.1));
+    /// let config = PidConfig::new(1.0, 0.3, 0.1).with_integral_limits(1.0, -1.0);
+    /// assert_eq!(controller.try_set_config(config), Err(ConfigError::InvertedIntegralLimits));
+    /// ```
+    fn try_set_config(&mut self, config: PidConfig<F>) -> Result<(), config::ConfigError> {
+        config.validate()?;
+        self.set_config(config);
+        Ok(())
+    }
+
+    /// Changes the controller's gains while keeping its state and the rest of its configuration.
+    ///
+    /// See also: [`Self::set_config()`]
+    /// ```
+    /// use advanced_pid::{prelude::*, Pid, PidConfig, PidGain};
+    ///
+    /// let mut controller = Pid::new(PidConfig::new(1.0, 0.3, 0.1).with_limits(-1.0, 1.0));
+    /// controller.set_gain(PidGain { kp: 2.0, ki: 0.5, kd: 0.0 });
+    /// assert_eq!(controller.config().max, 1.0);
+    /// ```
+    fn set_gain(&mut self, gain: PidGain<F>) {
+        let config = PidConfig {
+            gain,
+            ..self.config().clone()
+        };
+        self.set_config(config);
+    }
+}
+
+/// `ManualMode` is a trait that switches a controller between [automatic and manual mode](Mode) with bumpless transfer.
+pub trait ManualMode<F: Float = f32>: PidController<F> {
+    /// Switches the controller to manual mode with the specified output.
+    /// Calling it again while in manual mode changes the manual output.
+    ///
+    /// Keep calling [`PidController::update()`] while in manual mode: the controller tracks the manual output,
+    /// so that the output continues smoothly when switching back to automatic mode.
+    /// ```
+    /// use advanced_pid::{prelude::*, Mode, Pid, PidConfig};
+    ///
+    /// let mut controller = Pid::new(PidConfig::new(1.0, 0.3, 0.1));
+    /// controller.set_manual(0.5);
+    /// assert_eq!(controller.mode(), Mode::Manual(0.5));
+    /// assert_eq!(controller.update(1.0, 0.0, 0.1), 0.5);
+    /// ```
+    fn set_manual(&mut self, output: F);
+
+    /// Switches the controller back to automatic mode.
+    /// ```
+    /// use advanced_pid::{prelude::*, Mode, Pid, PidConfig};
+    ///
+    /// let mut controller = Pid::new(PidConfig::new(1.0, 0.3, 0.1));
+    /// controller.set_manual(0.5);
+    /// controller.update(1.0, 0.0, 0.1);
+    /// controller.set_auto();
+    /// assert_eq!(controller.mode(), Mode::Automatic);
+    /// ```
+    fn set_auto(&mut self);
+
+    /// Returns the current operating mode of the controller.
+    fn mode(&self) -> Mode<F>;
+}
+
+/// `Integrator` is a trait that gives access to the integrator of a controller.
+///
+/// It allows preloading the integral contribution from a known steady-state output at start-up, zeroing it on a fault,
+/// and limiting it independently of the output.
+/// For [`VelPid`], whose output is the integrator, the integral is the output, and the integrator limits clamp the output in addition to the output limits.
+pub trait Integrator<F: Float = f32>: Configurable<F> {
+    /// Returns the integral contribution to the output.
+    fn integral(&self) -> F;
+
+    /// Sets the integral contribution to the output, clamped to the integrator limits.
+    ///
+    /// It takes effect on the next update, where the rate limits still apply from the last output.
+    /// In manual mode, the integral keeps tracking the manual output.
+    /// ```
+    /// use advanced_pid::{prelude::*, Pid, PidConfig};
+    ///
+    /// let mut controller = Pid::new(PidConfig::new(1.0, 0.5, 0.0));
+    /// // Start from a steady-state output of 0.4.
+    /// controller.set_integral(0.4);
+    /// assert_eq!(controller.update(1.0, 1.0, 0.1), 0.4);
+    /// ```
+    fn set_integral(&mut self, integral: F);
+
+    /// Sets the integral contribution to zero, or to the nearest integrator limit.
+    fn reset_integral(&mut self) {
+        self.set_integral(F::ZERO);
+    }
+
+    /// Returns the lower and upper integrator limits.
+    fn integral_limits(&self) -> (F, F) {
+        (self.config().i_min, self.config().i_max)
+    }
+
+    /// Changes the integrator limits, and clamps the integral contribution to them.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`ConfigError::NanLimit`](config::ConfigError::NanLimit) or [`ConfigError::InvertedIntegralLimits`](config::ConfigError::InvertedIntegralLimits),
+    /// in which case the limits are left unchanged.
+    /// ```
+    /// use advanced_pid::{config::ConfigError, prelude::*, Pid, PidConfig};
+    ///
+    /// let mut controller = Pid::new(PidConfig::new(0.0, 1.0, 0.0));
+    /// controller.set_integral(0.8);
+    /// controller.set_integral_limits(-0.5, 0.5).unwrap();
+    /// assert_eq!(controller.integral(), 0.5);
+    /// assert_eq!(controller.set_integral_limits(0.5, -0.5), Err(ConfigError::InvertedIntegralLimits));
+    /// ```
+    fn set_integral_limits(&mut self, min: F, max: F) -> Result<(), config::ConfigError> {
+        config::check_limits(min, max, config::ConfigError::InvertedIntegralLimits)?;
+        let config = self.config().clone().with_integral_limits(min, max);
+        self.set_config(config);
+        Ok(())
+    }
+}
+
+/// `Snapshot` is a trait that takes the state of a controller and restores it (see the [`state`] module).
+pub trait Snapshot<F: Float = f32>: PidController<F> + Sized {
+    /// The plain-data state of the controller: [`state::PositionalState`] or [`state::VelocityState`].
+    type State: Copy;
+
+    /// Returns a snapshot of the controller's state.
+    fn snapshot(&self) -> Self::State;
+
+    /// Creates a controller with the specified configuration and state.
+    /// It continues exactly as the controller the state was taken from.
+    fn from_snapshot(config: PidConfig<F>, state: Self::State) -> Self;
+}
@@ -0,0 +1,285 @@
+//! The `metrics` module provides performance metrics of a step response.
+//!
+//! [`StepResponse`] accumulates samples of the set point, actual value, and controller output of a loop,
+//! either from a simulation with [`ClosedLoop`](crate::plant::ClosedLoop) or from logged data,
+//! and computes [`Metrics`] such as the rise time, overshoot, settling time, and error integrals.
+//! It does not store the samples, so it works in `no_std` environments without an allocator.
+//!
+//! # Examples
+//!
+//! ```rust
+//! use advanced_pid::metrics::StepResponse;
+//! use advanced_pid::plant::{ClosedLoop, FirstOrder};
+//! use advanced_pid::{prelude::*, Pid, PidConfig};
+//!
+//! let pid = Pid::new(PidConfig::new(2.0, 2.0, 0.0));
+//! let mut closed_loop = ClosedLoop::new(pid, FirstOrder::<f32, 5>::new(1.0, 1.0).with_dead_time(0.05));
+//!
+//! let mut response = StepResponse::new(0.0, 0.0);
+//! closed_loop.run(1.0, 0.01, 1000, |sample| response.push_sample(sample));
+//!
+//! let metrics = response.metrics();
+//! assert!(metrics.overshoot < 0.2);
+//! assert!(metrics.settling_time.is_some());
+//! ```
+use super::plant::Sample;
+use super::Float;
+
+/// `Metrics` holds the performance metrics of a step response.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Metrics<F = f32> {
+    /// Time to rise from 10% to 90% of the step, in seconds, or `None` if 90% was not reached.
+    pub rise_time: Option<F>,
+    /// Peak overshoot as a fraction of the step, or zero if the response did not exceed the set point.
+    pub overshoot: F,
+    /// Time from the start of the step until the response enters the settling band and stays there, in seconds,
+    /// or `None` if the last sample is outside the band.
+    pub settling_time: Option<F>,
+    /// Error of the last sample, the set point minus the actual value.
+    pub steady_state_error: F,
+    /// Integral of the absolute error (IAE).
+    pub iae: F,
+    /// Integral of the squared error (ISE).
+    pub ise: F,
+    /// Integral of the time-weighted absolute error (ITAE).
+    pub itae: F,
+    /// Total variation of the controller output, the sum of the absolute changes between samples.
+    /// It measures how much the actuator moves.
+    pub total_variation: F,
+}
+
+/// `StepResponse` accumulates the samples of a step response and computes its [`Metrics`].
+///
+/// The step is taken from the initial actual value to the set point of the first sample.
+/// The error integrals are computed with the rectangle rule, each sample weighting the time elapsed since the previous one.
+#[derive(Debug, Clone)]
+pub struct StepResponse<F = f32> {
+    settling_band: F,
+    start_time: F,
+    initial: F,
+    target: F,
+    pre_time: F,
+    pre_output: F,
+    rise_start: Option<F>,
+    rise_end: Option<F>,
+    peak: F,
+    settled_since: Option<F>,
+    last_error: F,
+    iae: F,
+    ise: F,
+    itae: F,
+    total_variation: F,
+}
+
+impl<F: Float> StepResponse<F> {
+    /// Creates a new `StepResponse` for a step applied at the specified start time, in seconds, from the specified initial actual value.
+    pub fn new(start_time: F, initial: F) -> Self {
+        Self {
+            settling_band: F::from_f64(0.02),
+            start_time,
+            initial,
+            target: F::NAN,
+            pre_time: start_time,
+            pre_output: F::NAN,
+            rise_start: None,
+            rise_end: None,
+            peak: F::ZERO,
+            settled_since: None,
+            last_error: F::NAN,
+            iae: F::ZERO,
+            ise: F::ZERO,
+            itae: F::ZERO,
+            total_variation: F::ZERO,
+        }
+    }
+
+    /// Returns a new `StepResponse` with the specified settling band, as a fraction of the step.
+    /// By default, the response is settled within 2% of the step.
+    pub fn with_settling_band(self, settling_band: F) -> Self {
+        Self {
+            settling_band,
+            ..self
+        }
+    }
+
+    /// Computes the metrics of the specified time series of time, set point, actual value, and controller output.
+    /// The first sample is the start of the step.
+    ///
+    /// # Panics
+    /// Panics if the series are empty or do not have the same length.
+    pub fn from_series(time: &[F], set_point: &[F], actual: &[F], output: &[F]) -> Self {
+        assert!(!time.is_empty(), "the series must not be empty");
+        assert!(
+            set_point.len() == time.len()
+                && actual.len() == time.len()
+                && output.len() == time.len(),
+            "the series must have the same length"
+        );
+        let mut response = Self::new(time[0], actual[0]);
+        for i in 0..time.len() {
+            response.push(time[i], set_point[i], actual[i], output[i]);
+        }
+        response
+    }
+
+    /// Adds a sample of the specified time, set point, actual value, and controller output.
+    /// Samples must be added in chronological order.
+    pub fn push(&mut self, time: F, set_point: F, actual: F, output: F) {
+        if self.target.is_nan() {
+            self.target = set_point;
+        }
+        let dt = time - self.pre_time;
+        self.pre_time = time;
+
+        let error = set_point - actual;
+        self.iae += error.abs() * dt;
+        self.ise += error * error * dt;
+        self.itae += (time - self.start_time) * error.abs() * dt;
+        if !self.pre_output.is_nan() {
+            self.total_variation += (output - self.pre_output).abs();
+        }
+        self.pre_output = output;
+        self.last_error = error;
+
+        let step = self.target - self.initial;
+        if step == F::ZERO {
+            return;
+        }
+        // Progress of the response as a fraction of the step, whichever its direction.
+        let progress = (actual - self.initial) / step;
+        if self.rise_start.is_none() && progress >= F::from_f64(0.1) {
+            self.rise_start = Some(time);
+        }
+        if self.rise_end.is_none() && progress >= F::from_f64(0.9) {
+            self.rise_end = Some(time);
+        }
+        self.peak = self.peak.max(progress - F::ONE);
+        if ((self.target - actual) / step).abs() > self.settling_band {
+            self.settled_since = None;
+        } else if self.settled_since.is_none() {
+            self.settled_since = Some(time);
+        }
+    }
+
+    /// Adds a sample of a [`ClosedLoop`](crate::plant::ClosedLoop) simulation.
+    pub fn push_sample(&mut self, sample: &Sample<F>) {
+        self.push(sample.time, sample.set_point, sample.actual, sample.output);
+    }
+
+    /// Returns the metrics of the samples added so far.
+    pub fn metrics(&self) -> Metrics<F> {
+        let rise_time = match (self.rise_start, self.rise_end) {
+            (Some(start), Some(end)) => Some(end - start),
+            _ => None,
+        };
+        Metrics {
+            rise_time,
+            overshoot: self.peak,
+            settling_time: self.settled_since.map(|time| time - self.start_time),
+            steady_state_error: self.last_error,
+            iae: self.iae,
+            ise: self.ise,
+            itae: self.itae,
+            total_variation: self.total_variation,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::plant::{ClosedLoop, FirstOrder};
+    use crate::{Ipd, Pid, PidConfig, PidController};
+
+    /// Samples `1 − e^(−t)` every millisecond for 10 s.
+    fn first_order_response() -> StepResponse<f64> {
+        // e^(−0.001)
+        let decay = 0.999_000_499_833_375;
+        let dt = 0.001;
+        let mut response = StepResponse::new(0.0, 0.0);
+        let mut remaining = 1.0;
+        for i in 1..=10_000 {
+            remaining *= decay;
+            response.push(i as f64 * dt, 1.0, 1.0 - remaining, remaining);
+        }
+        response
+    }
+
+    #[test]
+    fn test_metrics_first_order() {
+        let metrics = first_order_response().metrics();
+
+        // The rise time is ln(9), and the settling time is ln(50).
+        let rise_time = metrics.rise_time.unwrap();
+        assert!(
+            (rise_time - 2.1972).abs() < 2e-3,
+            "rise time: {}",
+            rise_time
+        );
+        let settling_time = metrics.settling_time.unwrap();
+        assert!(
+            (settling_time - 3.9120).abs() < 2e-3,
+            "settling time: {}",
+            settling_time
+        );
+        assert_eq!(metrics.overshoot, 0.0);
+        assert!((metrics.steady_state_error - 4.54e-5).abs() < 1e-7);
+        // The error integrals are 1, 1/2 and 1.
+        assert!((metrics.iae - 1.0).abs() < 2e-3, "IAE: {}", metrics.iae);
+        assert!((metrics.ise - 0.5).abs() < 2e-3, "ISE: {}", metrics.ise);
+        assert!((metrics.itae - 1.0).abs() < 2e-3, "ITAE: {}", metrics.itae);
+        // The output decays monotonically from 1 to 0.
+        assert!((metrics.total_variation - 1.0).abs() < 2e-3);
+    }
+
+    #[test]
+    fn test_metrics_from_series() {
+        let time = [0.0, 1.0, 2.0, 3.0, 4.0, 5.0];
+        let set_point = [-1.0, -1.0, -1.0, -1.0, -1.0, -1.0];
+        let actual = [1.0, 0.0, -1.5, -0.9, -1.0, -1.0];
+        let output = [0.0, -2.0, 1.0, 0.0, 0.0, 0.0];
+        let metrics = StepResponse::from_series(&time, &set_point, &actual, &output).metrics();
+
+        assert_eq!(metrics.rise_time, Some(1.0));
+        assert_eq!(metrics.overshoot, 0.25);
+        assert_eq!(metrics.settling_time, Some(4.0));
+        assert_eq!(metrics.steady_state_error, 0.0);
+        assert!((metrics.iae - 1.6).abs() < 1e-12);
+        assert_eq!(metrics.total_variation, 6.0);
+        assert!(metrics.ise > 0.0 && metrics.itae > metrics.iae);
+    }
+
+    #[test]
+    fn test_metrics_not_settled() {
+        let mut response = StepResponse::new(0.0, 0.0).with_settling_band(0.1);
+        response.push(1.0, 1.0, 0.5, 0.0);
+        assert_eq!(response.metrics().rise_time, None);
+        assert_eq!(response.metrics().settling_time, None);
+        response.push(2.0, 1.0, 0.95, 0.0);
+        assert_eq!(response.metrics().rise_time, Some(1.0));
+        assert_eq!(response.metrics().settling_time, Some(2.0));
+    }
+
+    #[test]
+    fn test_metrics_compare_controllers() {
+        fn simulate<C: PidController>(controller: C) -> Metrics {
+            let mut closed_loop = ClosedLoop::new(
+                controller,
+                FirstOrder::<f32, 10>::new(1.0, 1.0).with_dead_time(0.1),
+            );
+            let mut response = StepResponse::new(0.0, 0.0);
+            closed_loop.run(1.0, 0.01, 2000, |sample| response.push_sample(sample));
+            response.metrics()
+        }
+
+        let config = PidConfig::new(3.0, 3.0, 0.1);
+        let pid = simulate(Pid::new(config.clone()));
+        let ipd = simulate(Ipd::new(config));
+
+        // I-PD control does not kick the output on a set point change, so it overshoots and moves the actuator less.
+        assert!(ipd.overshoot < pid.overshoot);
+        assert!(ipd.total_variation < pid.total_variation);
+        assert!(pid.steady_state_error.abs() < 1e-3);
+        assert!(ipd.steady_state_error.abs() < 1e-3);
+    }
+}
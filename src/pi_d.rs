@@ -16,49 +16,152 @@
 //!
 //! println!("{}", pid.update(target, actual, dt));
 //! ```
-use super::FloatType;
+use super::Configurable;
+use super::Float;
+use super::Integrator;
+use super::ManualMode;
+use super::Mode;
 use super::PidConfig;
 use super::PidController;
+use super::Terms;
 
 /// `PiD` is a structure that implements the [`PidController`] trait.
 #[derive(Debug, Clone)]
-pub struct PiD {
-    config: PidConfig,
-    i_term: FloatType,
-    pre_actual: FloatType,
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(
+    feature = "serde",
+    serde(bound(
+        serialize = "F: Float + serde::Serialize",
+        deserialize = "F: Float + serde::Deserialize<'de>"
+    ))
+)]
+pub struct PiD<F = f32> {
+    config: PidConfig<F>,
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_float::nan"))]
+    i_term: F,
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_float::nan"))]
+    pre_i_error: F,
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_float::nan"))]
+    pre_actual: F,
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_float::nan"))]
+    d_term_lpf: F,
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_float::nan"))]
+    pre_output: F,
+    mode: Mode<F>,
+    #[cfg_attr(feature = "serde", serde(skip))]
+    terms: Option<Terms<F>>,
 }
 
-impl Default for PiD {
+impl<F: Float> Default for PiD<F> {
     /// Creates a new `PiD` with the default configuration.
     fn default() -> Self {
         Self::new(PidConfig::default())
     }
 }
 
-impl PidController for PiD {
+crate::state::impl_positional_snapshot!(PiD, pre_actual);
+
+impl<F: Float> PidController<F> for PiD<F> {
     /// Creates a new `PiD` with the specified configuration.
-    fn new(config: PidConfig) -> Self {
+    fn new(config: PidConfig<F>) -> Self {
         Self {
             config,
-            i_term: 0.0,
-            pre_actual: FloatType::NAN,
+            i_term: F::ZERO,
+            pre_i_error: F::NAN,
+            pre_actual: F::NAN,
+            d_term_lpf: F::ZERO,
+            pre_output: F::ZERO,
+            mode: Mode::Automatic,
+            terms: None,
         }
     }
 
     /// Updates the `PiD` controller with the specified set point, actual value, and time delta.
     /// Returns the controller output.
-    fn update(&mut self, set_point: FloatType, actual: FloatType, dt: FloatType) -> FloatType {
+    fn update(&mut self, set_point: F, actual: F, dt: F) -> F {
+        self.update_with_feedforward(set_point, actual, F::ZERO, dt)
+    }
+
+    /// Updates the `PiD` controller with the specified set point, actual value, feedforward value, and time delta.
+    /// Returns the controller output.
+    fn update_with_feedforward(&mut self, set_point: F, actual: F, feedforward: F, dt: F) -> F {
+        let (actual, i_error) = self.config.deadband(set_point, actual);
         let error = set_point - actual;
-        self.i_term += error * dt;
         let d_term = if self.pre_actual.is_nan() {
-            0.0
+            F::ZERO
         } else {
             (actual - self.pre_actual) / dt
         };
-        let output = self.config.gain.kp * error + self.config.gain.ki * self.i_term
-            - self.config.gain.kd * d_term;
+        let d_term = self
+            .config
+            .filter_derivative(&mut self.d_term_lpf, d_term, dt);
+        let p_term = self.config.gain.kp * error;
+        let d_term = -(self.config.gain.kd * d_term);
+        let others = p_term + d_term + feedforward;
+        let limits = self.config.output_limits(self.pre_output, dt);
+        let (output, unclamped) = match self.mode {
+            Mode::Automatic => self.config.integrate(
+                &mut self.i_term,
+                self.config.gain.ki * self.config.integral_error(i_error, self.pre_i_error) * dt,
+                others,
+                dt,
+                limits,
+            ),
+            Mode::Manual(output) => self.config.track(&mut self.i_term, output, others, limits),
+        };
+        self.pre_output = output;
+        self.pre_i_error = i_error;
         self.pre_actual = actual;
-        output.clamp(self.config.min, self.config.max)
+        let terms = Terms {
+            p: p_term,
+            i: self.i_term,
+            d: d_term,
+            feedforward,
+            unclamped,
+            output: self.config.output_deadband.apply(output),
+            saturated: output <= limits.0 || output >= limits.1,
+        };
+        self.terms = Some(terms);
+        terms.output
+    }
+
+    fn last_terms(&self) -> Option<Terms<F>> {
+        self.terms
+    }
+}
+
+impl<F: Float> Configurable<F> for PiD<F> {
+    fn config(&self) -> &PidConfig<F> {
+        &self.config
+    }
+
+    fn set_config(&mut self, config: PidConfig<F>) {
+        self.i_term = self.i_term.clamp(config.i_min, config.i_max);
+        self.config = config;
+    }
+}
+
+impl<F: Float> ManualMode<F> for PiD<F> {
+    fn set_manual(&mut self, output: F) {
+        self.mode = Mode::Manual(output);
+    }
+
+    fn set_auto(&mut self) {
+        self.mode = Mode::Automatic;
+    }
+
+    fn mode(&self) -> Mode<F> {
+        self.mode
+    }
+}
+
+impl<F: Float> Integrator<F> for PiD<F> {
+    fn integral(&self) -> F {
+        self.i_term
+    }
+
+    fn set_integral(&mut self, integral: F) {
+        self.i_term = integral.clamp(self.config.i_min, self.config.i_max);
     }
 }
 
@@ -112,4 +215,70 @@ mod tests {
         let output = pid.update(1.0, 1.0, 1.0);
         assert_eq!(output, -1.0);
     }
+
+    #[test]
+    fn test_pi_d_controller_terms() {
+        let mut pid = PiD::new(PidConfig::new(1.0, 0.0, 1.0));
+
+        pid.update(1.0, 0.0, 1.0);
+        let terms = pid.update_detailed(1.0, 0.5, 0.0, 1.0).unwrap();
+        assert_eq!((terms.p, terms.i, terms.d), (0.5, 0.0, -0.5));
+        assert_eq!((terms.unclamped, terms.output), (0.0, 0.0));
+    }
+
+    #[test]
+    fn test_pi_d_controller_integrator() {
+        let mut pid = PiD::new(PidConfig::new(0.0, 1.0, 0.0).with_integral_limits(0.0, 1.0));
+
+        pid.set_integral(-0.5);
+        assert_eq!(pid.integral(), 0.0);
+        pid.set_integral(0.5);
+        assert_eq!(pid.update(1.0, 1.0, 1.0), 0.5);
+        pid.reset_integral();
+        assert_eq!(pid.update(1.0, 1.0, 1.0), 0.0);
+    }
+
+    #[test]
+    fn test_pi_d_controller_tustin() {
+        let config = PidConfig::new(0.0, 1.0, 0.0)
+            .with_discretization(crate::config::Discretization::Tustin);
+        let mut pid = PiD::new(config);
+
+        assert_eq!(pid.update(0.0, 0.0, 1.0), 0.0);
+        assert_eq!(pid.update(1.0, 0.0, 1.0), 0.5);
+        assert_eq!(pid.update(2.0, 0.0, 1.0), 2.0);
+    }
+
+    #[test]
+    fn test_pi_d_controller_conditional_integration() {
+        let config = PidConfig::new(0.0, 1.0, 0.0)
+            .with_limits(-1.0, 1.0)
+            .with_anti_windup(crate::config::AntiWindup::Conditional);
+        let mut pid = PiD::new(config);
+
+        assert_eq!(pid.update(1.0, 0.0, 1.0), 1.0);
+        assert_eq!(pid.update(1.0, 0.0, 1.0), 1.0);
+        assert_eq!(pid.update(1.0, 0.0, 1.0), 1.0);
+        assert_eq!(pid.update(-1.0, 0.0, 1.0), 0.0);
+    }
+
+    #[test]
+    fn test_pi_d_controller_back_calculation() {
+        let config = PidConfig::new(0.0, 1.0, 0.0)
+            .with_limits(-1.0, 1.0)
+            .with_anti_windup(crate::config::AntiWindup::BackCalculation { kt: 1.0 });
+        let mut pid = PiD::new(config);
+
+        assert_eq!(pid.update(1.0, 0.0, 1.0), 1.0);
+        assert_eq!(pid.update(1.0, 0.0, 1.0), 1.0);
+        assert_eq!(pid.update(-1.0, 0.0, 1.0), 0.0);
+    }
+
+    #[test]
+    fn test_pi_d_controller_feedforward() {
+        let mut pid = PiD::new(PidConfig::new(1.0, 0.0, 1.0));
+
+        assert_eq!(pid.update_with_feedforward(1.0, 0.5, 0.25, 1.0), 0.75);
+        assert_eq!(pid.update_with_feedforward(1.0, 0.5, -0.25, 1.0), 0.25);
+    }
 }
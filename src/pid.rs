@@ -16,50 +16,152 @@
 //!
 //! println!("{}", pid.update(target, actual, dt));
 //! ```
-use super::FloatType;
+use super::Configurable;
+use super::Float;
+use super::Integrator;
+use super::ManualMode;
+use super::Mode;
 use super::PidConfig;
 use super::PidController;
+use super::Terms;
 
 /// `Pid` is a structure that implements the [`PidController`] trait.
 #[derive(Debug, Clone)]
-pub struct Pid {
-    config: PidConfig,
-    i_term: FloatType,
-    pre_error: FloatType,
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(
+    feature = "serde",
+    serde(bound(
+        serialize = "F: Float + serde::Serialize",
+        deserialize = "F: Float + serde::Deserialize<'de>"
+    ))
+)]
+pub struct Pid<F = f32> {
+    config: PidConfig<F>,
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_float::nan"))]
+    i_term: F,
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_float::nan"))]
+    pre_i_error: F,
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_float::nan"))]
+    pre_error: F,
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_float::nan"))]
+    d_term_lpf: F,
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_float::nan"))]
+    pre_output: F,
+    mode: Mode<F>,
+    #[cfg_attr(feature = "serde", serde(skip))]
+    terms: Option<Terms<F>>,
 }
 
-impl Default for Pid {
+impl<F: Float> Default for Pid<F> {
     /// Creates a new `Pid` with the default configuration.
     fn default() -> Self {
         Self::new(PidConfig::default())
     }
 }
 
-impl PidController for Pid {
+crate::state::impl_positional_snapshot!(Pid, pre_error);
+
+impl<F: Float> PidController<F> for Pid<F> {
     /// Creates a new `Pid` with the specified configuration.
-    fn new(config: PidConfig) -> Self {
+    fn new(config: PidConfig<F>) -> Self {
         Self {
             config,
-            i_term: 0.0,
-            pre_error: FloatType::NAN,
+            i_term: F::ZERO,
+            pre_i_error: F::NAN,
+            pre_error: F::NAN,
+            d_term_lpf: F::ZERO,
+            pre_output: F::ZERO,
+            mode: Mode::Automatic,
+            terms: None,
         }
     }
 
     /// Updates the `Pid` controller with the specified set point, actual value, and time delta.
     /// Returns the controller output.
-    fn update(&mut self, set_point: FloatType, actual: FloatType, dt: FloatType) -> FloatType {
+    fn update(&mut self, set_point: F, actual: F, dt: F) -> F {
+        self.update_with_feedforward(set_point, actual, F::ZERO, dt)
+    }
+
+    /// Updates the `Pid` controller with the specified set point, actual value, feedforward value, and time delta.
+    /// Returns the controller output.
+    fn update_with_feedforward(&mut self, set_point: F, actual: F, feedforward: F, dt: F) -> F {
+        let (actual, i_error) = self.config.deadband(set_point, actual);
         let error = set_point - actual;
-        self.i_term += error * dt;
         let d_term = if self.pre_error.is_nan() {
-            0.0
+            F::ZERO
         } else {
             (error - self.pre_error) / dt
         };
-        let output = self.config.gain.kp * error
-            + self.config.gain.ki * self.i_term
-            + self.config.gain.kd * d_term;
+        let d_term = self
+            .config
+            .filter_derivative(&mut self.d_term_lpf, d_term, dt);
+        let p_term = self.config.gain.kp * error;
+        let d_term = self.config.gain.kd * d_term;
+        let others = p_term + d_term + feedforward;
+        let limits = self.config.output_limits(self.pre_output, dt);
+        let (output, unclamped) = match self.mode {
+            Mode::Automatic => self.config.integrate(
+                &mut self.i_term,
+                self.config.gain.ki * self.config.integral_error(i_error, self.pre_i_error) * dt,
+                others,
+                dt,
+                limits,
+            ),
+            Mode::Manual(output) => self.config.track(&mut self.i_term, output, others, limits),
+        };
+        self.pre_output = output;
+        self.pre_i_error = i_error;
         self.pre_error = error;
-        output.clamp(self.config.min, self.config.max)
+        let terms = Terms {
+            p: p_term,
+            i: self.i_term,
+            d: d_term,
+            feedforward,
+            unclamped,
+            output: self.config.output_deadband.apply(output),
+            saturated: output <= limits.0 || output >= limits.1,
+        };
+        self.terms = Some(terms);
+        terms.output
+    }
+
+    fn last_terms(&self) -> Option<Terms<F>> {
+        self.terms
+    }
+}
+
+impl<F: Float> Configurable<F> for Pid<F> {
+    fn config(&self) -> &PidConfig<F> {
+        &self.config
+    }
+
+    fn set_config(&mut self, config: PidConfig<F>) {
+        self.i_term = self.i_term.clamp(config.i_min, config.i_max);
+        self.config = config;
+    }
+}
+
+impl<F: Float> ManualMode<F> for Pid<F> {
+    fn set_manual(&mut self, output: F) {
+        self.mode = Mode::Manual(output);
+    }
+
+    fn set_auto(&mut self) {
+        self.mode = Mode::Automatic;
+    }
+
+    fn mode(&self) -> Mode<F> {
+        self.mode
+    }
+}
+
+impl<F: Float> Integrator<F> for Pid<F> {
+    fn integral(&self) -> F {
+        self.i_term
+    }
+
+    fn set_integral(&mut self, integral: F) {
+        self.i_term = integral.clamp(self.config.i_min, self.config.i_max);
     }
 }
 
@@ -122,4 +224,358 @@ mod tests {
         let output = pid.update(-1.0, 0.0, 1.0);
         assert_eq!(output, -0.5);
     }
+
+    #[test]
+    fn test_pid_controller_integral_limits() {
+        let config = PidConfig::new(0.0, 1.0, 0.0).with_integral_limits(-1.5, 1.5);
+        let mut pid = Pid::new(config);
+
+        assert_eq!(pid.update(1.0, 0.0, 1.0), 1.0);
+        assert_eq!(pid.update(1.0, 0.0, 1.0), 1.5);
+        assert_eq!(pid.update(1.0, 0.0, 1.0), 1.5);
+        assert_eq!(pid.update(-1.0, 0.0, 1.0), 0.5);
+    }
+
+    #[test]
+    fn test_pid_controller_derivative_filter() {
+        let config = PidConfig::new(0.0, 0.0, 1.0)
+            .with_derivative_filter(crate::config::DerivativeFilter::TimeConstant(1.0));
+        let mut pid = Pid::new(config);
+
+        let output = pid.update(1.0, 0.0, 1.0);
+        assert_eq!(output, 0.0);
+        let output = pid.update(1.0, 1.0, 1.0);
+        assert_eq!(output, -0.5);
+        let output = pid.update(1.0, 1.0, 1.0);
+        assert_eq!(output, -0.25);
+    }
+
+    #[test]
+    fn test_pid_controller_discretization_ramp() {
+        use crate::config::Discretization;
+        // The integral of the ramp error `e(t) = t` is `t^2 / 2`.
+        let dt = 0.25;
+        for (discretization, offset) in [
+            (Discretization::ForwardEuler, -0.5),
+            (Discretization::BackwardEuler, 0.5),
+            (Discretization::Tustin, 0.0),
+        ] {
+            let config = PidConfig::new(0.0, 1.0, 0.0).with_discretization(discretization);
+            let mut pid: Pid<f64> = Pid::new(config);
+            let mut output = 0.0;
+            for k in 0..=8 {
+                output = pid.update(k as f64 * dt, 0.0, dt);
+            }
+            // Forward and backward Euler are off by `± t dt / 2`.
+            assert_eq!(output, 2.0 + offset * 2.0 * dt, "{:?}", discretization);
+        }
+    }
+
+    #[test]
+    fn test_pid_controller_discretization_prewarped() {
+        use crate::config::{DerivativeFilter, Discretization};
+        // The continuous filtered derivative `s / (s + 1)` has the gain `1 / sqrt(2)` at `ω = 1`.
+        let (dt, cos, sin) = (0.5, 0.8775825618903728, 0.479425538604203);
+        let amplitude = |discretization| {
+            let config = PidConfig::new(0.0, 0.0, 1.0)
+                .with_derivative_filter(DerivativeFilter::TimeConstant(1.0))
+                .with_discretization(discretization);
+            let mut pid: Pid<f64> = Pid::new(config);
+            // `sin(k dt)` generated by recurrence.
+            let (mut pre, mut set_point) = (-sin, 0.0);
+            let (mut y0, mut y1) = (0.0, 0.0);
+            for _ in 0..80 {
+                (y0, y1) = (y1, pid.update(set_point, 0.0, dt));
+                (pre, set_point) = (set_point, 2.0 * cos * set_point - pre);
+            }
+            crate::float::sqrt((y0 * y0 + y1 * y1 - 2.0 * y0 * y1 * cos) / (sin * sin))
+        };
+        let expected = core::f64::consts::FRAC_1_SQRT_2;
+        let prewarped = amplitude(Discretization::TustinPrewarped);
+        assert!((prewarped - expected).abs() < 1e-9, "{}", prewarped);
+        assert!((amplitude(Discretization::Tustin) - expected).abs() > 5e-3);
+        assert!((amplitude(Discretization::BackwardEuler) - expected).abs() > 5e-2);
+    }
+
+    #[test]
+    fn test_pid_controller_bumpless_transfer() {
+        let mut pid = Pid::new(PidConfig::new(1.0, 1.0, 0.0));
+
+        pid.update(1.0, 0.0, 0.1);
+        pid.set_manual(0.7);
+        assert_eq!(pid.mode(), crate::Mode::Manual(0.7));
+        assert_eq!(pid.update(1.0, 0.5, 0.1), 0.7);
+        assert_eq!(pid.update(1.0, 0.5, 0.1), 0.7);
+        pid.set_auto();
+        assert_eq!(pid.mode(), crate::Mode::Automatic);
+        let output = pid.update(1.0, 0.5, 0.1);
+        assert!((output - 0.75).abs() < 1e-6, "output: {}", output);
+    }
+
+    #[test]
+    fn test_pid_controller_manual_limits() {
+        let config = PidConfig::new(1.0, 1.0, 0.0).with_limits(-0.5, 0.5);
+        let mut pid = Pid::new(config);
+
+        pid.set_manual(2.0);
+        assert_eq!(pid.update(1.0, 0.0, 0.1), 0.5);
+    }
+
+    #[test]
+    fn test_pid_controller_set_config() {
+        let mut pid = Pid::new(PidConfig::new(1.0, 1.0, 0.0));
+        pid.update(1.0, 0.0, 1.0);
+        pid.update(1.0, 0.5, 1.0);
+
+        let mut kept = pid.clone();
+        kept.set_gain(crate::PidGain {
+            kp: 1.0,
+            ki: 4.0,
+            kd: 0.0,
+        });
+        assert_eq!(kept.update(1.0, 1.0, 1.0), 1.5);
+        assert_eq!(pid.update(1.0, 1.0, 1.0), 1.5);
+
+        let mut reset = pid.clone();
+        reset.reset_config(PidConfig::new(1.0, 4.0, 0.0));
+        assert_eq!(reset.update(1.0, 1.0, 1.0), 0.0);
+    }
+
+    #[test]
+    fn test_pid_controller_set_config_integral_limits() {
+        let mut pid = Pid::new(PidConfig::new(0.0, 1.0, 0.0));
+        pid.update(1.0, 0.0, 1.0);
+        pid.update(1.0, 0.0, 1.0);
+
+        pid.set_config(PidConfig::new(0.0, 1.0, 0.0).with_integral_limits(-1.0, 1.0));
+        assert_eq!(pid.update(0.0, 0.0, 1.0), 1.0);
+    }
+
+    #[test]
+    fn test_pid_controller_rate_limits() {
+        let config = PidConfig::new(1.0, 0.0, 0.0)
+            .with_limits(-1.0, 1.0)
+            .with_rate_limits(2.0, 4.0);
+        let mut pid = Pid::new(config);
+
+        assert_eq!(pid.update(1.0, 0.0, 0.25), 0.5);
+        assert_eq!(pid.update(1.0, 0.0, 0.25), 1.0);
+        assert_eq!(pid.update(1.0, 0.0, 0.25), 1.0);
+        assert_eq!(pid.update(-1.0, 0.0, 0.25), 0.0);
+        assert_eq!(pid.update(-1.0, 0.0, 0.25), -1.0);
+    }
+
+    #[test]
+    fn test_pid_controller_rate_limits_non_finite() {
+        let config = PidConfig::new(1.0, 0.0, 0.0)
+            .with_limits(-1.0, 1.0)
+            .with_rate_limits(2.0, 4.0);
+        let mut pid = Pid::new(config);
+
+        assert!(pid
+            .update_with_feedforward(1.0, 0.0, f32::NAN, 0.25)
+            .is_nan());
+        // The rate limits restart from the next finite output.
+        assert_eq!(pid.update(1.0, 0.0, 0.25), 1.0);
+        assert_eq!(pid.update(-1.0, 0.0, 0.25), 0.0);
+    }
+
+    #[test]
+    fn test_pid_controller_rate_limits_negative_dt() {
+        let config = PidConfig::new(1.0, 0.0, 0.0)
+            .with_limits(-1.0, 1.0)
+            .with_rate_limits(2.0, 4.0);
+        let mut pid = Pid::new(config);
+
+        assert_eq!(pid.update(1.0, 0.0, 0.25), 0.5);
+        // A negative time delta skips the rate limits instead of inverting them.
+        assert_eq!(pid.update(1.0, 0.0, -0.25), 1.0);
+        assert_eq!(pid.update(-1.0, 0.0, 0.25), 0.0);
+    }
+
+    #[test]
+    fn test_pid_controller_rate_limits_anti_windup() {
+        let config = PidConfig::new(0.0, 1.0, 0.0)
+            .with_rate_limits(1.0, 1.0)
+            .with_anti_windup(crate::config::AntiWindup::Conditional);
+        let mut pid = Pid::new(config);
+
+        assert_eq!(pid.update(10.0, 0.0, 0.5), 0.5);
+        assert_eq!(pid.update(10.0, 0.0, 0.5), 1.0);
+        // The integral stops at the rate-limited output, so it reverses at once.
+        assert_eq!(pid.update(-1.0, 0.0, 0.5), 0.5);
+    }
+
+    #[test]
+    fn test_pid_controller_error_deadband() {
+        let config = PidConfig::new(1.0, 1.0, 0.0).with_error_deadband(0.25, true);
+        let mut pid = Pid::new(config);
+
+        assert_eq!(pid.update(1.0, 0.5, 1.0), 1.0);
+        assert_eq!(pid.update(1.0, 0.875, 1.0), 0.5);
+        assert_eq!(pid.update(1.0, 1.125, 1.0), 0.5);
+
+        let config = PidConfig::new(1.0, 1.0, 0.0).with_error_deadband(0.25, false);
+        let mut pid = Pid::new(config);
+
+        assert_eq!(pid.update(1.0, 0.5, 1.0), 1.0);
+        assert_eq!(pid.update(1.0, 0.875, 1.0), 0.625);
+    }
+
+    #[test]
+    fn test_pid_controller_output_deadband() {
+        let config = PidConfig::new(1.0, 0.0, 0.0)
+            .with_output_deadband(crate::config::OutputDeadband::Nearest(0.5));
+        let mut pid = Pid::new(config);
+
+        assert_eq!(pid.update(1.0, 0.875, 1.0), 0.0);
+        assert_eq!(pid.update(1.0, 0.625, 1.0), 0.5);
+        assert_eq!(pid.update(1.0, 1.375, 1.0), -0.5);
+        assert_eq!(pid.update(1.0, 0.25, 1.0), 0.75);
+    }
+
+    #[test]
+    fn test_pid_controller_output_deadband_rate_limits() {
+        let config = PidConfig::new(1.0, 0.0, 0.0)
+            .with_rate_limits(1.0, 1.0)
+            .with_output_deadband(crate::config::OutputDeadband::Zero(0.5));
+        let mut pid: Pid<f64> = Pid::new(config);
+
+        // The rate limits apply before the deadband, so the returned output jumps when it leaves the deadband.
+        let outputs: [f64; 6] = core::array::from_fn(|_| pid.update(1.0, 0.0, 0.1));
+        let expected = [0.0, 0.0, 0.0, 0.0, 0.5, 0.6];
+        for (output, expected) in outputs.iter().zip(expected) {
+            assert!((output - expected).abs() < 1e-12, "{:?}", outputs);
+        }
+    }
+
+    #[test]
+    fn test_pid_controller_feedforward() {
+        let config = PidConfig::new(1.0, 1.0, 0.0)
+            .with_limits(-1.0, 1.0)
+            .with_anti_windup(crate::config::AntiWindup::Conditional);
+        let mut pid = Pid::new(config);
+
+        assert_eq!(pid.update_with_feedforward(1.0, 1.0, 0.5, 1.0), 0.5);
+        assert_eq!(pid.update_with_feedforward(1.0, 0.5, 0.5, 1.0), 1.0);
+        // The integral stopped at the limit including the feedforward value.
+        assert_eq!(pid.update_with_feedforward(1.0, 1.0, 0.5, 1.0), 0.5);
+    }
+
+    #[test]
+    fn test_pid_controller_terms() {
+        let config = PidConfig::new(1.0, 0.5, 0.25).with_limits(-1.0, 3.0);
+        let mut pid = Pid::new(config);
+        assert_eq!(pid.last_terms(), None);
+
+        let terms = pid.update_detailed(1.0, 0.0, 0.5, 1.0).unwrap();
+        assert_eq!(
+            (terms.p, terms.i, terms.d, terms.feedforward),
+            (1.0, 0.5, 0.0, 0.5)
+        );
+        assert_eq!((terms.unclamped, terms.output), (2.0, 2.0));
+        assert!(!terms.saturated);
+
+        let terms = pid.update_detailed(1.0, 0.5, 0.5, 1.0).unwrap();
+        assert_eq!((terms.p, terms.i, terms.d), (0.5, 0.75, -0.125));
+        assert_eq!(terms.output, 1.625);
+
+        let output = pid.update(1.0, -2.0, 1.0);
+        let terms = pid.last_terms().unwrap();
+        assert_eq!((terms.unclamped, terms.output), (5.875, output));
+        assert_eq!(output, 3.0);
+        assert!(terms.saturated);
+    }
+
+    #[test]
+    fn test_pid_controller_integrator() {
+        let config = PidConfig::new(1.0, 1.0, 0.0).with_integral_limits(-1.0, 1.0);
+        let mut pid = Pid::new(config);
+
+        pid.set_integral(0.5);
+        assert_eq!(pid.integral(), 0.5);
+        assert_eq!(pid.update(1.0, 1.0, 1.0), 0.5);
+        // The integrator limits apply to the integral only.
+        pid.set_integral(2.0);
+        assert_eq!(pid.integral(), 1.0);
+        assert_eq!(pid.update(1.0, -1.0, 1.0), 3.0);
+        pid.set_integral_limits(-0.25, 0.25).unwrap();
+        assert_eq!(pid.integral_limits(), (-0.25, 0.25));
+        assert_eq!(
+            pid.set_integral_limits(0.25, -0.25),
+            Err(crate::config::ConfigError::InvertedIntegralLimits)
+        );
+        assert_eq!(pid.integral_limits(), (-0.25, 0.25));
+        assert_eq!(pid.integral(), 0.25);
+        pid.reset_integral();
+        assert_eq!(pid.update(1.0, 1.0, 1.0), 0.0);
+    }
+
+    #[test]
+    fn test_pid_controller_try_set_config() {
+        let mut pid = Pid::new(PidConfig::new(1.0, 1.0, 0.0));
+
+        let config = PidConfig::new(1.0, 1.0, 0.0).with_limits(1.0, -1.0);
+        assert_eq!(
+            pid.try_set_config(config),
+            Err(crate::config::ConfigError::InvertedLimits)
+        );
+        assert_eq!(pid.config().max, f32::INFINITY);
+        let config = PidConfig::new(2.0, 1.0, 0.0).with_limits(-1.0, 1.0);
+        assert_eq!(pid.try_set_config(config), Ok(()));
+        assert_eq!(pid.update(1.0, 0.0, 1.0), 1.0);
+    }
+
+    #[test]
+    fn test_pid_controller_integrator_generic() {
+        fn preload<C: Integrator>(controller: &mut C, output: f32) {
+            controller.set_integral(output);
+        }
+
+        let mut pid = Pid::new(PidConfig::new(1.0, 1.0, 0.0));
+        let mut vel_pid = crate::VelPid::new(PidConfig::new(1.0, 1.0, 0.0));
+        preload(&mut pid, 0.75);
+        preload(&mut vel_pid, 0.75);
+        assert_eq!(pid.update(1.0, 1.0, 0.1), 0.75);
+        assert_eq!(vel_pid.update(1.0, 1.0, 0.1), 0.75);
+    }
+
+    #[test]
+    fn test_pid_controller_terms_anti_windup() {
+        use crate::config::AntiWindup;
+        for (anti_windup, i) in [
+            (AntiWindup::None, 1.0),
+            (AntiWindup::Conditional, 0.0),
+            (AntiWindup::BackCalculation { kt: 1.0 }, 0.0),
+        ] {
+            let config = PidConfig::new(1.0, 1.0, 0.0)
+                .with_limits(-1.0, 1.0)
+                .with_anti_windup(anti_windup);
+            let mut pid = Pid::new(config);
+
+            // The unclamped output is the sum before the anti-windup strategy adjusts the integral.
+            let terms = pid.update_detailed(1.0, 0.0, 0.0, 1.0).unwrap();
+            assert_eq!((terms.p, terms.i), (1.0, i), "{:?}", anti_windup);
+            assert_eq!(
+                (terms.unclamped, terms.output),
+                (2.0, 1.0),
+                "{:?}",
+                anti_windup
+            );
+            assert!(terms.saturated);
+        }
+
+        let mut pid = Pid::new(PidConfig::new(1.0, 1.0, 0.0).with_limits(-1.0, 1.0));
+        pid.set_manual(1.5);
+        let terms = pid.update_detailed(1.0, 0.0, 0.0, 1.0).unwrap();
+        assert_eq!((terms.unclamped, terms.output), (1.5, 1.0));
+    }
+
+    #[test]
+    fn test_pid_try_new() {
+        let pid = Pid::try_new(PidConfig::new(1.0, 0.5, 0.1).with_limits(-1.0, 1.0));
+        assert!(pid.is_ok());
+        let pid = Pid::try_new(PidConfig::new(1.0, 0.5, -0.1));
+        assert_eq!(pid.unwrap_err(), crate::config::ConfigError::NegativeGain);
+    }
 }
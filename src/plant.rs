@@ -0,0 +1,519 @@
+//! The `plant` module provides discretised process models for closing loops in simulations and tests.
+//!
+//! Every model implements the [`Plant`] trait, which steps it with an input over a time delta.
+//! [`Disturbed`] adds a load disturbance to the input of a plant and measurement noise to its output,
+//! and [`ClosedLoop`] steps any [`PidController`] against a plant.
+//!
+//! The models are discretised with the backward (implicit) Euler method where it matters, so they stay stable for any time delta.
+//!
+//! # Examples
+//!
+//! ```rust
+//! use advanced_pid::plant::{ClosedLoop, Disturbed, FirstOrder};
+//! use advanced_pid::{prelude::*, Pid, PidConfig};
+//!
+//! let pid = Pid::new(PidConfig::new(1.0, 0.8, 0.0));
+//! // First order lag with a gain of 2, a time constant of 1 s and a dead time of 0.5 s, buffered in 5 samples of 0.1 s.
+//! let plant = FirstOrder::<f32, 5>::new(2.0, 1.0).with_dead_time(0.5);
+//! let plant = Disturbed::new(plant).with_noise(0.01, 42);
+//! let mut closed_loop = ClosedLoop::new(pid, plant);
+//!
+//! let target = 1.0;
+//! let dt = 0.1;
+//!
+//! closed_loop.run(target, dt, 100, |sample| println!("{:?}", sample));
+//! ```
+use super::Float;
+use super::PidController;
+
+/// `Plant` is a trait for process models that can be stepped in a simulation.
+pub trait Plant<F: Float = f32> {
+    /// Steps the plant with the specified input held over the specified time delta.
+    /// Returns the output at the end of the step.
+    fn step(&mut self, input: F, dt: F) -> F;
+
+    /// Returns the current output.
+    fn output(&self) -> F;
+}
+
+/// `FirstOrder` is a first order lag with dead time `K e^(−Ls) / (Ts + 1)`.
+///
+/// The dead time `L` is in seconds, like [`Fopdt::dead_time`](crate::tuning::Fopdt::dead_time), and zero by default:
+/// the input applied at a time only affects the output `L` seconds later.
+/// The past inputs are kept in a buffer of `N` samples, which must hold at least `L / dt` of them;
+/// with a shorter buffer, the dead time is cut to `N` steps.
+/// It is rounded to the nearest whole number of steps.
+#[derive(Debug, Clone)]
+pub struct FirstOrder<F = f32, const N: usize = 0> {
+    gain: F,
+    time_constant: F,
+    dead_time: F,
+    // The start time and the input of the buffered steps.
+    inputs: [(F, F); N],
+    len: usize,
+    next: usize,
+    time: F,
+    output: F,
+}
+
+impl<F: Float, const N: usize> FirstOrder<F, N> {
+    /// Creates a new `FirstOrder` with the specified static gain and time constant, in seconds, at rest.
+    pub fn new(gain: F, time_constant: F) -> Self {
+        Self {
+            gain,
+            time_constant,
+            dead_time: F::ZERO,
+            inputs: [(F::ZERO, F::ZERO); N],
+            len: 0,
+            next: 0,
+            time: F::ZERO,
+            output: F::ZERO,
+        }
+    }
+
+    /// Returns a new `FirstOrder` with the specified dead time in seconds.
+    pub fn with_dead_time(self, dead_time: F) -> Self {
+        Self { dead_time, ..self }
+    }
+
+    /// Buffers the input of a step, and returns the input applied the dead time before the start of the step.
+    fn delay(&mut self, input: F, dt: F) -> F {
+        if N == 0 {
+            return input;
+        }
+        let start = self.time;
+        self.time += dt;
+        // Half a step rounds the dead time to whole steps, whatever the rounding of the elapsed time.
+        let applied_at = start - self.dead_time + dt / F::from_f64(2.0);
+        let delayed = if applied_at >= start {
+            input
+        } else {
+            self.buffered(applied_at)
+        };
+        self.inputs[self.next] = (start, input);
+        self.next = (self.next + 1) % N;
+        self.len = (self.len + 1).min(N);
+        delayed
+    }
+
+    /// Returns the buffered input that was applied at the specified time.
+    fn buffered(&self, applied_at: F) -> F {
+        let mut oldest = F::ZERO;
+        for age in 1..=self.len {
+            let (time, value) = self.inputs[(self.next + N - age) % N];
+            if time <= applied_at {
+                return value;
+            }
+            oldest = value;
+        }
+        // The plant was at rest before the first step, unless the buffer is too short to tell.
+        if self.len == N {
+            oldest
+        } else {
+            F::ZERO
+        }
+    }
+}
+
+impl<F: Float, const N: usize> Plant<F> for FirstOrder<F, N> {
+    fn step(&mut self, input: F, dt: F) -> F {
+        let input = self.delay(input, dt);
+        self.output += (self.gain * input - self.output) * dt / (self.time_constant + dt);
+        self.output
+    }
+
+    fn output(&self) -> F {
+        self.output
+    }
+}
+
+/// `SecondOrder` is a second order system `K ωn² / (s² + 2ζωn s + ωn²)`.
+#[derive(Debug, Clone)]
+pub struct SecondOrder<F = f32> {
+    gain: F,
+    natural_frequency: F,
+    damping_ratio: F,
+    output: F,
+    velocity: F,
+}
+
+impl<F: Float> SecondOrder<F> {
+    /// Creates a new `SecondOrder` with the specified static gain, natural frequency, in radians per second, and damping ratio, at rest.
+    pub fn new(gain: F, natural_frequency: F, damping_ratio: F) -> Self {
+        Self {
+            gain,
+            natural_frequency,
+            damping_ratio,
+            output: F::ZERO,
+            velocity: F::ZERO,
+        }
+    }
+}
+
+impl<F: Float> Plant<F> for SecondOrder<F> {
+    fn step(&mut self, input: F, dt: F) -> F {
+        let wn = self.natural_frequency;
+        let damping = F::from_f64(2.0) * self.damping_ratio * wn * dt;
+        let stiffness = wn * wn * dt;
+        // Backward Euler, solved for the velocity at the end of the step.
+        self.velocity = (self.velocity + stiffness * (self.gain * input - self.output))
+            / (F::ONE + damping + stiffness * dt);
+        self.output += self.velocity * dt;
+        self.output
+    }
+
+    fn output(&self) -> F {
+        self.output
+    }
+}
+
+/// `Integrating` is an integrating process `K / s`, such as a tank level.
+#[derive(Debug, Clone)]
+pub struct Integrating<F = f32> {
+    gain: F,
+    output: F,
+}
+
+impl<F: Float> Integrating<F> {
+    /// Creates a new `Integrating` with the specified gain, at rest.
+    pub fn new(gain: F) -> Self {
+        Self {
+            gain,
+            output: F::ZERO,
+        }
+    }
+}
+
+impl<F: Float> Plant<F> for Integrating<F> {
+    fn step(&mut self, input: F, dt: F) -> F {
+        self.output += self.gain * input * dt;
+        self.output
+    }
+
+    fn output(&self) -> F {
+        self.output
+    }
+}
+
+/// `DoubleIntegrator` is a double integrator `K / s²`, such as a mass driven by a force.
+#[derive(Debug, Clone)]
+pub struct DoubleIntegrator<F = f32> {
+    gain: F,
+    output: F,
+    velocity: F,
+}
+
+impl<F: Float> DoubleIntegrator<F> {
+    /// Creates a new `DoubleIntegrator` with the specified gain, at rest.
+    pub fn new(gain: F) -> Self {
+        Self {
+            gain,
+            output: F::ZERO,
+            velocity: F::ZERO,
+        }
+    }
+
+    /// Returns the current velocity, the derivative of the output.
+    pub fn velocity(&self) -> F {
+        self.velocity
+    }
+}
+
+impl<F: Float> Plant<F> for DoubleIntegrator<F> {
+    fn step(&mut self, input: F, dt: F) -> F {
+        self.velocity += self.gain * input * dt;
+        self.output += self.velocity * dt;
+        self.output
+    }
+
+    fn output(&self) -> F {
+        self.output
+    }
+}
+
+/// `Disturbed` wraps a plant with a load disturbance added to its input and uniform measurement noise added to its output.
+///
+/// The noise is drawn from a xorshift generator, so a simulation is reproducible from its seed.
+#[derive(Debug, Clone)]
+pub struct Disturbed<P, F = f32> {
+    plant: P,
+    load: F,
+    noise: F,
+    state: u32,
+    measurement: F,
+}
+
+impl<P, F> Disturbed<P, F>
+where
+    P: Plant<F>,
+    F: Float,
+{
+    /// Creates a new `Disturbed` without disturbance nor noise.
+    pub fn new(plant: P) -> Self {
+        let measurement = plant.output();
+        Self {
+            plant,
+            load: F::ZERO,
+            noise: F::ZERO,
+            state: 1,
+            measurement,
+        }
+    }
+
+    /// Returns a new `Disturbed` with the specified load disturbance.
+    pub fn with_load(self, load: F) -> Self {
+        Self { load, ..self }
+    }
+
+    /// Returns a new `Disturbed` with measurement noise uniformly distributed within the specified amplitude,
+    /// drawn from the specified seed.
+    pub fn with_noise(self, amplitude: F, seed: u32) -> Self {
+        Self {
+            noise: amplitude,
+            // Xorshift gets stuck at zero.
+            state: seed.max(1),
+            ..self
+        }
+    }
+
+    /// Changes the load disturbance, e.g. to simulate a step disturbance during a run.
+    pub fn set_load(&mut self, load: F) {
+        self.load = load;
+    }
+
+    /// Returns a reference to the wrapped plant, whose output is free of noise.
+    pub fn plant(&self) -> &P {
+        &self.plant
+    }
+
+    /// Returns a uniformly distributed random value in `[-1, 1)`.
+    fn uniform(&mut self) -> F {
+        self.state ^= self.state << 13;
+        self.state ^= self.state >> 17;
+        self.state ^= self.state << 5;
+        F::from_f64(self.state as f64 / 2_147_483_648.0 - 1.0)
+    }
+}
+
+impl<P, F> Plant<F> for Disturbed<P, F>
+where
+    P: Plant<F>,
+    F: Float,
+{
+    fn step(&mut self, input: F, dt: F) -> F {
+        let output = self.plant.step(input + self.load, dt);
+        self.measurement = if self.noise == F::ZERO {
+            output
+        } else {
+            output + self.noise * self.uniform()
+        };
+        self.measurement
+    }
+
+    /// Returns the last measurement, including the noise.
+    fn output(&self) -> F {
+        self.measurement
+    }
+}
+
+/// `Sample` is the state of a [`ClosedLoop`] after a step.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Sample<F = f32> {
+    /// Time since the start of the simulation, in seconds.
+    pub time: F,
+    /// Set point of the step.
+    pub set_point: F,
+    /// Controller output applied to the plant during the step.
+    pub output: F,
+    /// Plant output at the end of the step.
+    pub actual: F,
+}
+
+/// `ClosedLoop` steps a controller against a plant.
+///
+/// On every step, the controller is updated with the current plant output, and its output is applied to the plant over the time delta.
+#[derive(Debug, Clone)]
+pub struct ClosedLoop<C, P, F = f32> {
+    controller: C,
+    plant: P,
+    time: F,
+}
+
+impl<C, P, F> ClosedLoop<C, P, F>
+where
+    C: PidController<F>,
+    P: Plant<F>,
+    F: Float,
+{
+    /// Creates a new `ClosedLoop` with the specified controller and plant.
+    pub fn new(controller: C, plant: P) -> Self {
+        Self {
+            controller,
+            plant,
+            time: F::ZERO,
+        }
+    }
+
+    /// Steps the loop once with the specified set point and time delta.
+    pub fn step(&mut self, set_point: F, dt: F) -> Sample<F> {
+        let output = self.controller.update(set_point, self.plant.output(), dt);
+        let actual = self.plant.step(output, dt);
+        self.time += dt;
+        Sample {
+            time: self.time,
+            set_point,
+            output,
+            actual,
+        }
+    }
+
+    /// Steps the loop the specified number of times with the specified set point and time delta,
+    /// calling the specified closure with every sample.
+    /// Returns the last sample, or `None` if no step was made.
+    pub fn run(
+        &mut self,
+        set_point: F,
+        dt: F,
+        steps: usize,
+        mut on_sample: impl FnMut(&Sample<F>),
+    ) -> Option<Sample<F>> {
+        let mut last = None;
+        for _ in 0..steps {
+            let sample = self.step(set_point, dt);
+            on_sample(&sample);
+            last = Some(sample);
+        }
+        last
+    }
+
+    /// Returns the time since the start of the simulation, in seconds.
+    pub fn time(&self) -> F {
+        self.time
+    }
+
+    /// Returns a reference to the controller.
+    pub fn controller(&self) -> &C {
+        &self.controller
+    }
+
+    /// Returns a mutable reference to the controller.
+    pub fn controller_mut(&mut self) -> &mut C {
+        &mut self.controller
+    }
+
+    /// Returns a reference to the plant.
+    pub fn plant(&self) -> &P {
+        &self.plant
+    }
+
+    /// Returns a mutable reference to the plant.
+    pub fn plant_mut(&mut self) -> &mut P {
+        &mut self.plant
+    }
+
+    /// Consumes the loop and returns the controller and the plant.
+    pub fn into_inner(self) -> (C, P) {
+        (self.controller, self.plant)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Pid, PidConfig};
+
+    #[test]
+    fn test_first_order() {
+        let mut plant: FirstOrder<f32> = FirstOrder::new(2.0, 1.0);
+        assert_eq!(plant.step(1.0, 1.0), 1.0);
+        assert_eq!(plant.step(1.0, 1.0), 1.5);
+        assert_eq!(plant.output(), 1.5);
+    }
+
+    #[test]
+    fn test_first_order_dead_time() {
+        let mut plant = FirstOrder::<f32, 2>::new(1.0, 0.0).with_dead_time(0.2);
+        assert_eq!(plant.step(1.0, 0.1), 0.0);
+        assert_eq!(plant.step(2.0, 0.1), 0.0);
+        assert_eq!(plant.step(3.0, 0.1), 1.0);
+        assert_eq!(plant.step(3.0, 0.1), 2.0);
+
+        // The dead time does not depend on the time delta.
+        let mut plant = FirstOrder::<f32, 4>::new(1.0, 0.0).with_dead_time(0.2);
+        assert_eq!(plant.step(1.0, 0.05), 0.0);
+        assert_eq!(plant.step(2.0, 0.15), 0.0);
+        assert_eq!(plant.step(3.0, 0.1), 2.0);
+        assert_eq!(plant.step(4.0, 0.2), 3.0);
+        assert_eq!(plant.step(4.0, 0.2), 4.0);
+
+        // A buffer that is too short cuts the dead time.
+        let mut plant = FirstOrder::<f32, 1>::new(1.0, 0.0).with_dead_time(0.2);
+        assert_eq!(plant.step(1.0, 0.1), 0.0);
+        assert_eq!(plant.step(2.0, 0.1), 1.0);
+        assert_eq!(plant.step(3.0, 0.1), 2.0);
+    }
+
+    #[test]
+    fn test_second_order() {
+        // Settles at its static gain, with an overshoot of about 16% for a damping ratio of 0.5.
+        let mut plant: SecondOrder<f64> = SecondOrder::new(2.0, 1.0, 0.5);
+        let mut peak: f64 = 0.0;
+        for _ in 0..20_000 {
+            peak = peak.max(plant.step(1.0, 0.001));
+        }
+        assert!((plant.output() - 2.0).abs() < 1e-3);
+        assert!((peak / 2.0 - 1.163).abs() < 0.01, "peak: {}", peak);
+        // Stable for large time deltas.
+        let mut plant: SecondOrder<f64> = SecondOrder::new(1.0, 10.0, 0.1);
+        for _ in 0..100 {
+            plant.step(1.0, 1.0);
+        }
+        assert!((plant.output() - 1.0).abs() < 1e-3);
+    }
+
+    #[test]
+    fn test_integrators() {
+        let mut plant: Integrating<f32> = Integrating::new(2.0);
+        assert_eq!(plant.step(1.0, 0.5), 1.0);
+        assert_eq!(plant.step(-1.0, 0.5), 0.0);
+
+        let mut plant: DoubleIntegrator<f32> = DoubleIntegrator::new(2.0);
+        assert_eq!(plant.step(1.0, 0.5), 0.5);
+        assert_eq!(plant.step(0.0, 0.5), 1.0);
+        assert_eq!(plant.velocity(), 1.0);
+    }
+
+    #[test]
+    fn test_disturbed() {
+        let mut plant = Disturbed::new(Integrating::new(1.0)).with_load(1.0);
+        assert_eq!(plant.step(0.0, 1.0), 1.0);
+        plant.set_load(0.0);
+        assert_eq!(plant.step(0.0, 1.0), 1.0);
+
+        let mut plant = Disturbed::new(Integrating::<f32>::new(1.0)).with_noise(0.1, 7);
+        let mut sum = 0.0;
+        for _ in 0..1000 {
+            let measurement = plant.step(0.0, 1.0);
+            assert!((-0.1..0.1).contains(&measurement));
+            sum += measurement;
+        }
+        assert!((sum / 1000.0).abs() < 0.01);
+        assert_eq!(plant.plant().output(), 0.0);
+    }
+
+    #[test]
+    fn test_closed_loop_rejects_load_disturbance() {
+        let pid = Pid::new(PidConfig::new(1.0, 2.0, 0.0));
+        let plant = Disturbed::new(FirstOrder::<f32, 3>::new(1.0, 1.0).with_dead_time(0.03))
+            .with_load(-0.5);
+        let mut closed_loop = ClosedLoop::new(pid, plant);
+
+        let mut steps = 0;
+        let last = closed_loop.run(1.0, 0.01, 2000, |_| steps += 1).unwrap();
+        assert_eq!(steps, 2000);
+        assert!((closed_loop.time() - 20.0).abs() < 1e-3);
+        assert!((last.actual - 1.0).abs() < 1e-3, "actual: {}", last.actual);
+        // The integral action makes up for the load disturbance.
+        assert!((last.output - 1.5).abs() < 1e-3, "output: {}", last.output);
+    }
+}
@@ -1,2 +1,6 @@
 //! The `prelude` module provides a prelude for the `advanced_pid` crate.
+pub use super::Configurable;
+pub use super::Integrator;
+pub use super::ManualMode;
 pub use super::PidController;
+pub use super::Snapshot;
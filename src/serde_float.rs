@@ -0,0 +1,70 @@
+//! Serde helpers for floats that are not finite.
+//!
+//! JSON and TOML cannot represent infinity nor NaN, so they are serialized as `None` (`null` in JSON, omitted in TOML),
+//! and `None` is deserialized as the value that the field means when it is absent:
+//! an unbounded limit or rate, or no previous sample.
+use super::Float;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+/// Serializes finite values as `Some`, and infinity and NaN as `None`.
+pub(crate) fn serialize<F, S>(value: &F, serializer: S) -> Result<S::Ok, S::Error>
+where
+    F: Float + Serialize,
+    S: Serializer,
+{
+    if value.is_finite() {
+        serializer.serialize_some(value)
+    } else {
+        serializer.serialize_none()
+    }
+}
+
+fn deserialize_or<'de, F, D>(deserializer: D, none: F) -> Result<F, D::Error>
+where
+    F: Float + Deserialize<'de>,
+    D: Deserializer<'de>,
+{
+    Ok(Option::deserialize(deserializer)?.unwrap_or(none))
+}
+
+/// Upper limits and rates, which are unbounded when `None`.
+pub(crate) mod infinity {
+    pub(crate) use super::serialize;
+    use super::*;
+
+    pub(crate) fn deserialize<'de, F, D>(deserializer: D) -> Result<F, D::Error>
+    where
+        F: Float + Deserialize<'de>,
+        D: Deserializer<'de>,
+    {
+        deserialize_or(deserializer, F::INFINITY)
+    }
+}
+
+/// Lower limits, which are unbounded when `None`.
+pub(crate) mod neg_infinity {
+    pub(crate) use super::serialize;
+    use super::*;
+
+    pub(crate) fn deserialize<'de, F, D>(deserializer: D) -> Result<F, D::Error>
+    where
+        F: Float + Deserialize<'de>,
+        D: Deserializer<'de>,
+    {
+        deserialize_or(deserializer, F::NEG_INFINITY)
+    }
+}
+
+/// Previous samples, which are NaN before the first update, and controller state, which is NaN after a NaN input.
+pub(crate) mod nan {
+    pub(crate) use super::serialize;
+    use super::*;
+
+    pub(crate) fn deserialize<'de, F, D>(deserializer: D) -> Result<F, D::Error>
+    where
+        F: Float + Deserialize<'de>,
+        D: Deserializer<'de>,
+    {
+        deserialize_or(deserializer, F::NAN)
+    }
+}
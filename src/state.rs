@@ -0,0 +1,239 @@
+//! The `state` module provides plain-data snapshots of the controllers' state.
+//!
+//! A snapshot holds everything a controller carries from one update to the next, but not its configuration.
+//! A controller rebuilt from its configuration and a snapshot continues bit-identically to the original,
+//! so that a restarted process or a standby node can take over a running loop.
+//!
+//! [`PositionalState`] is the state of [`Pid`](crate::Pid), [`PiD`](crate::PiD), [`Ipd`](crate::Ipd) and [`TwoDofPid`](crate::TwoDofPid),
+//! and [`VelocityState`] is the state of [`VelPid`](crate::VelPid).
+//! They are taken and restored through the [`Snapshot`](crate::Snapshot) trait.
+//!
+//! # Examples
+//!
+//! ```rust
+//! use advanced_pid::{prelude::*, Pid, PidConfig};
+//!
+//! let config = PidConfig::new(1.0, 0.3, 0.1).with_limits(-1.0, 1.0);
+//! let mut pid = Pid::new(config.clone());
+//! pid.update(1.0, 0.0, 0.1);
+//!
+//! let state = pid.snapshot();
+//! let mut restored = Pid::from_snapshot(config, state);
+//! assert_eq!(restored.update(1.0, 0.2, 0.1), pid.update(1.0, 0.2, 0.1));
+//! ```
+#[cfg(feature = "serde")]
+use super::Float;
+use super::Mode;
+
+/// `PositionalState` is the state of a position form controller.
+///
+/// The NaN values mean that there is no previous sample, before the first update.
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(
+    feature = "serde",
+    serde(bound(
+        serialize = "F: Float + serde::Serialize",
+        deserialize = "F: Float + serde::Deserialize<'de>"
+    ))
+)]
+pub struct PositionalState<F = f32> {
+    /// Integral contribution to the output.
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_float::nan"))]
+    pub integral: F,
+    /// Previous error seen by the integral, or NaN.
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_float::nan"))]
+    pub pre_integral_error: F,
+    /// Previous value seen by the derivative, or NaN:
+    /// the error for `Pid`, the actual value for `PiD` and `Ipd`, and the weighted error for `TwoDofPid`.
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_float::nan"))]
+    pub pre_derivative_input: F,
+    /// State of the derivative filter.
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_float::nan"))]
+    pub derivative_filter: F,
+    /// Last output, before the output deadband.
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_float::nan"))]
+    pub output: F,
+    /// Operating mode.
+    pub mode: Mode<F>,
+}
+
+/// `VelocityState` is the state of a velocity form controller.
+///
+/// The NaN values mean that there is no previous sample, before the first update.
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(
+    feature = "serde",
+    serde(bound(
+        serialize = "F: Float + serde::Serialize",
+        deserialize = "F: Float + serde::Deserialize<'de>"
+    ))
+)]
+pub struct VelocityState<F = f32> {
+    /// Last output, before the output deadband.
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_float::nan"))]
+    pub output: F,
+    /// Previous error.
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_float::nan"))]
+    pub pre_error: F,
+    /// Previous error seen by the integral, or NaN.
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_float::nan"))]
+    pub pre_integral_error: F,
+    /// Previous rate of change of the error, or NaN.
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_float::nan"))]
+    pub pre_error_rate: F,
+    /// State of the derivative filter.
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_float::nan"))]
+    pub derivative_filter: F,
+    /// Previous feedforward value.
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_float::nan"))]
+    pub pre_feedforward: F,
+    /// Operating mode.
+    pub mode: Mode<F>,
+}
+
+/// Implements [`Snapshot`](crate::Snapshot) for a position form controller,
+/// whose previous derivative input is held in the specified field.
+macro_rules! impl_positional_snapshot {
+    ($controller:ident, $pre_derivative_input:ident) => {
+        impl<F: $crate::Float> $crate::Snapshot<F> for $controller<F> {
+            type State = $crate::state::PositionalState<F>;
+
+            fn snapshot(&self) -> Self::State {
+                $crate::state::PositionalState {
+                    integral: self.i_term,
+                    pre_integral_error: self.pre_i_error,
+                    pre_derivative_input: self.$pre_derivative_input,
+                    derivative_filter: self.d_term_lpf,
+                    output: self.pre_output,
+                    mode: self.mode,
+                }
+            }
+
+            fn from_snapshot(config: $crate::PidConfig<F>, state: Self::State) -> Self {
+                Self {
+                    config,
+                    i_term: state.integral,
+                    pre_i_error: state.pre_integral_error,
+                    $pre_derivative_input: state.pre_derivative_input,
+                    d_term_lpf: state.derivative_filter,
+                    pre_output: state.output,
+                    mode: state.mode,
+                    terms: None,
+                }
+            }
+        }
+    };
+}
+pub(crate) use impl_positional_snapshot;
+
+#[cfg(test)]
+mod tests {
+    use crate::{prelude::*, Ipd, PiD, Pid, PidConfig, TwoDofPid, VelPid};
+
+    fn config() -> PidConfig {
+        PidConfig::new(1.2, 0.7, 0.05)
+            .with_limits(-1.0, 1.0)
+            .with_rate_limits(4.0, 4.0)
+            .with_derivative_filter(crate::config::DerivativeFilter::TimeConstant(0.02))
+            .with_discretization(crate::config::Discretization::Tustin)
+            .with_setpoint_weights(0.5, 0.25)
+    }
+
+    /// Drives a loop with the controller and checks, every few updates,
+    /// that a controller restored from a snapshot continues bit-identically.
+    fn assert_restores<C: Snapshot + Clone>() {
+        let mut pid = C::new(config());
+        let mut actual = 0.0;
+        for k in 0..50 {
+            if k % 10 == 0 {
+                let mut restored = C::from_snapshot(config(), pid.snapshot());
+                let mut original = pid.clone();
+                for j in 0..5 {
+                    let dt = 0.01 + 0.001 * j as f32;
+                    let expected = original.update(1.0, actual, dt);
+                    assert_eq!(
+                        restored.update(1.0, actual, dt).to_bits(),
+                        expected.to_bits()
+                    );
+                }
+            }
+            actual += 0.3 * (pid.update(1.0, actual, 0.01) - actual);
+        }
+    }
+
+    #[test]
+    fn test_snapshot_restore() {
+        assert_restores::<Pid>();
+        assert_restores::<PiD>();
+        assert_restores::<Ipd>();
+        assert_restores::<TwoDofPid>();
+        assert_restores::<VelPid>();
+    }
+
+    /// Checks that a controller and its snapshot survive a JSON round trip,
+    /// before the first update, when the state has NaN values, and after it.
+    #[cfg(feature = "serde")]
+    fn assert_serde_round_trip<C>()
+    where
+        C: Snapshot + serde::Serialize + serde::de::DeserializeOwned,
+        C::State: serde::Serialize + serde::de::DeserializeOwned,
+    {
+        let mut pid = C::new(config());
+        for actual in [0.0, 0.3, 0.5, 0.8] {
+            let json = serde_json::to_string(&pid).unwrap();
+            let mut restored: C = serde_json::from_str(&json).unwrap();
+            let json = serde_json::to_string(&pid.snapshot()).unwrap();
+            let mut from_state = C::from_snapshot(config(), serde_json::from_str(&json).unwrap());
+
+            let expected = pid.update(1.0, actual, 0.1).to_bits();
+            assert_eq!(restored.update(1.0, actual, 0.1).to_bits(), expected);
+            assert_eq!(from_state.update(1.0, actual, 0.1).to_bits(), expected);
+        }
+    }
+
+    /// Checks that a controller and its snapshot survive a JSON round trip after a NaN feedforward has reached their state.
+    #[cfg(feature = "serde")]
+    fn assert_serde_round_trip_after_nan<C>()
+    where
+        C: Snapshot + serde::Serialize + serde::de::DeserializeOwned,
+        C::State: serde::Serialize + serde::de::DeserializeOwned,
+    {
+        let same = |a: f32, b: f32| a.to_bits() == b.to_bits() || (a.is_nan() && b.is_nan());
+        let mut pid = C::new(config());
+        pid.update(1.0, 0.2, 0.1);
+        assert!(pid
+            .update_with_feedforward(1.0, 0.4, f32::NAN, 0.1)
+            .is_nan());
+        let json = serde_json::to_string(&pid).unwrap();
+        let mut restored: C = serde_json::from_str(&json).unwrap();
+        let json = serde_json::to_string(&pid.snapshot()).unwrap();
+        let mut from_state = C::from_snapshot(config(), serde_json::from_str(&json).unwrap());
+        for actual in [0.5, 0.6, 0.7] {
+            let expected = pid.update(1.0, actual, 0.1);
+            assert!(same(restored.update(1.0, actual, 0.1), expected));
+            assert!(same(from_state.update(1.0, actual, 0.1), expected));
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "serde")]
+    fn test_serde_round_trip_after_nan() {
+        assert_serde_round_trip_after_nan::<Pid>();
+        assert_serde_round_trip_after_nan::<PiD>();
+        assert_serde_round_trip_after_nan::<Ipd>();
+        assert_serde_round_trip_after_nan::<TwoDofPid>();
+        assert_serde_round_trip_after_nan::<VelPid>();
+    }
+
+    #[test]
+    #[cfg(feature = "serde")]
+    fn test_serde_round_trip() {
+        assert_serde_round_trip::<Pid>();
+        assert_serde_round_trip::<PiD>();
+        assert_serde_round_trip::<Ipd>();
+        assert_serde_round_trip::<TwoDofPid>();
+        assert_serde_round_trip::<VelPid>();
+    }
+}
@@ -0,0 +1,376 @@
+//! The `tuning` module provides classic tuning rules that derive PID gains from a process model or from relay results.
+//!
+//! Model-based rules ([`ModelRule`]) take a first-order plus dead time model ([`Fopdt`]),
+//! which can be fitted to an open-loop step response.
+//! Ultimate-based rules ([`UltimateRule`]) take the [`Ultimate`] gain and period,
+//! which are measured by the [relay autotuner](crate::autotune) or by raising a proportional gain until the loop oscillates.
+//!
+//! Every rule is available for P, PI and PID control ([`Structure`]).
+//! Rules that do not define a structure use their gains of the next richer structure without the extra action.
+//! The gains are returned for the parallel form used by the controllers: `kp`, `ki = kp / Ti`, and `kd = kp Td`.
+//!
+//! # Examples
+//!
+//! ```rust
+//! use advanced_pid::tuning::{Fopdt, ModelRule, Structure};
+//! use advanced_pid::{prelude::*, Pid};
+//!
+//! let model = Fopdt {
+//!     gain: 2.0,
+//!     time_constant: 10.0,
+//!     dead_time: 1.0,
+//! };
+//! let config = model.config(ModelRule::Simc(1.0), Structure::Pi);
+//! let mut pid = Pid::new(config.with_limits(0.0, 1.0));
+//!
+//! let target = 1.0;
+//! let actual = 0.0;
+//! let dt = 0.1;
+//!
+//! println!("{}", pid.update(target, actual, dt));
+//! ```
+use super::config::{Config, Gain};
+use super::Float;
+
+/// `Structure` selects which actions the tuned controller uses.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Structure {
+    /// Proportional action only.
+    P,
+    /// Proportional and integral actions.
+    Pi,
+    /// Proportional, integral, and derivative actions.
+    #[default]
+    Pid,
+}
+
+impl Structure {
+    /// Returns the gains for the specified proportional gain, integral time, and derivative time,
+    /// dropping the actions that are not part of this structure.
+    fn gain<F: Float>(self, kp: F, ti: F, td: F) -> Gain<F> {
+        let ki = || kp / ti;
+        match self {
+            Self::P => Gain {
+                kp,
+                ki: F::ZERO,
+                kd: F::ZERO,
+            },
+            Self::Pi => Gain {
+                kp,
+                ki: ki(),
+                kd: F::ZERO,
+            },
+            Self::Pid => Gain {
+                kp,
+                ki: ki(),
+                kd: kp * td,
+            },
+        }
+    }
+}
+
+/// `Fopdt` is a first-order plus dead time process model `K e^(−Ls) / (Ts + 1)`.
+///
+/// The model-based rules require a positive time constant and a positive dead time.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Fopdt<F = f32> {
+    /// Static gain `K` of the process, in units of the actual value per unit of output.
+    pub gain: F,
+    /// Time constant `T`, in seconds.
+    pub time_constant: F,
+    /// Dead time `L`, in seconds.
+    pub dead_time: F,
+}
+
+/// `ModelRule` selects the rule used to derive gains from a [`Fopdt`] model.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ModelRule<F = f32> {
+    /// Ziegler–Nichols step response (reaction curve) rule. Fast, with a quarter amplitude decay.
+    ZieglerNichols,
+    /// Cohen–Coon rule. Suited to processes with a relatively large dead time.
+    CohenCoon,
+    /// Skogestad's SIMC rule with the specified closed-loop time constant, in seconds.
+    /// A closed-loop time constant equal to the dead time is a good trade-off between speed and robustness.
+    Simc(F),
+    /// Lambda (IMC) rule with the specified closed-loop time constant, in seconds.
+    Lambda(F),
+    /// Åström and Hägglund's AMIGO rule. Robust, with a maximum sensitivity of about 1.4.
+    Amigo,
+}
+
+impl<F: Float> Fopdt<F> {
+    /// Returns the gains derived from the model with the specified rule and controller structure.
+    pub fn gain(&self, rule: ModelRule<F>, structure: Structure) -> Gain<F> {
+        let c = F::from_f64;
+        let (k, t, l) = (self.gain, self.time_constant, self.dead_time);
+        let (kp, ti, td) = match (rule, structure) {
+            (ModelRule::ZieglerNichols, Structure::P) => (t / (k * l), F::ZERO, F::ZERO),
+            (ModelRule::ZieglerNichols, Structure::Pi) => {
+                (c(0.9) * t / (k * l), l / c(0.3), F::ZERO)
+            }
+            (ModelRule::ZieglerNichols, Structure::Pid) => {
+                (c(1.2) * t / (k * l), c(2.0) * l, c(0.5) * l)
+            }
+            (ModelRule::CohenCoon, structure) => {
+                let r = l / t;
+                let scale = t / (k * l);
+                match structure {
+                    Structure::P => (scale * (F::ONE + r / c(3.0)), F::ZERO, F::ZERO),
+                    Structure::Pi => (
+                        scale * (c(0.9) + r / c(12.0)),
+                        l * (c(30.0) + c(3.0) * r) / (c(9.0) + c(20.0) * r),
+                        F::ZERO,
+                    ),
+                    Structure::Pid => (
+                        scale * (c(4.0 / 3.0) + r / c(4.0)),
+                        l * (c(32.0) + c(6.0) * r) / (c(13.0) + c(8.0) * r),
+                        c(4.0) * l / (c(11.0) + c(2.0) * r),
+                    ),
+                }
+            }
+            (ModelRule::Simc(tc), Structure::P | Structure::Pi) => {
+                (t / (k * (tc + l)), t.min(c(4.0) * (tc + l)), F::ZERO)
+            }
+            (ModelRule::Simc(tc), Structure::Pid) => {
+                // The series PID of the SIMC rule for time-delay processes, converted to the parallel form.
+                let td = l / c(3.0);
+                let t = t + td;
+                let kp = t / (k * (tc + l));
+                let ti = t.min(c(4.0) * (tc + l));
+                (kp * (F::ONE + td / ti), ti + td, ti * td / (ti + td))
+            }
+            (ModelRule::Lambda(lambda), Structure::P | Structure::Pi) => {
+                (t / (k * (lambda + l)), t, F::ZERO)
+            }
+            (ModelRule::Lambda(lambda), Structure::Pid) => {
+                // IMC rule with a first-order Padé approximation of the dead time.
+                let half = l / c(2.0);
+                (
+                    (t + half) / (k * (lambda + half)),
+                    t + half,
+                    t * l / (c(2.0) * t + l),
+                )
+            }
+            (ModelRule::Amigo, Structure::P | Structure::Pi) => {
+                let sum = l + t;
+                let kp = c(0.15) / k + (c(0.35) - l * t / (sum * sum)) * t / (k * l);
+                let ti =
+                    c(0.35) * l + c(13.0) * l * t * t / (t * t + c(12.0) * l * t + c(7.0) * l * l);
+                (kp, ti, F::ZERO)
+            }
+            (ModelRule::Amigo, Structure::Pid) => (
+                (c(0.2) + c(0.45) * t / l) / k,
+                (c(0.4) * l + c(0.8) * t) / (l + c(0.1) * t) * l,
+                c(0.5) * l * t / (c(0.3) * l + t),
+            ),
+        };
+        structure.gain(kp, ti, td)
+    }
+
+    /// Returns a `Config` with the gains derived from the model with the specified rule and controller structure.
+    pub fn config(&self, rule: ModelRule<F>, structure: Structure) -> Config<F> {
+        self.gain(rule, structure).into()
+    }
+}
+
+/// `Ultimate` holds the ultimate gain and period of a loop.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Ultimate<F = f32> {
+    /// Ultimate gain: the proportional gain at which the closed loop oscillates.
+    pub gain: F,
+    /// Ultimate period of the oscillation, in seconds.
+    pub period: F,
+}
+
+/// `UltimateRule` selects the rule used to derive gains from the [`Ultimate`] gain and period.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum UltimateRule {
+    /// Ziegler–Nichols ultimate sensitivity rule. Fast, with a large overshoot.
+    #[default]
+    ZieglerNichols,
+    /// Tyreus–Luyben rule. More robust and less oscillatory than Ziegler–Nichols.
+    TyreusLuyben,
+    /// Pessen integral rule. Aggressive, for fast disturbance rejection.
+    PessenIntegral,
+    /// Ziegler–Nichols variant with some overshoot.
+    SomeOvershoot,
+    /// Ziegler–Nichols variant with little or no overshoot.
+    NoOvershoot,
+}
+
+impl UltimateRule {
+    /// Returns the proportional gain, integral time, and derivative time as fractions of the ultimate gain and period.
+    fn coefficients(self, structure: Structure) -> (f64, f64, f64) {
+        match (self, structure) {
+            (Self::ZieglerNichols, Structure::P) => (0.5, 0.0, 0.0),
+            (Self::ZieglerNichols, Structure::Pi) => (0.45, 1.0 / 1.2, 0.0),
+            (Self::ZieglerNichols, Structure::Pid) => (0.6, 0.5, 0.125),
+            (Self::TyreusLuyben, Structure::P | Structure::Pi) => (1.0 / 3.2, 2.2, 0.0),
+            (Self::TyreusLuyben, Structure::Pid) => (1.0 / 2.2, 2.2, 1.0 / 6.3),
+            (Self::PessenIntegral, _) => (0.7, 0.4, 0.15),
+            (Self::SomeOvershoot, _) => (1.0 / 3.0, 0.5, 1.0 / 3.0),
+            (Self::NoOvershoot, _) => (0.2, 0.5, 1.0 / 3.0),
+        }
+    }
+}
+
+impl<F: Float> Ultimate<F> {
+    /// Returns the gains derived from the ultimate gain and period with the specified rule and controller structure.
+    pub fn gain(&self, rule: UltimateRule, structure: Structure) -> Gain<F> {
+        let (kp, ti, td) = rule.coefficients(structure);
+        structure.gain(
+            F::from_f64(kp) * self.gain,
+            F::from_f64(ti) * self.period,
+            F::from_f64(td) * self.period,
+        )
+    }
+
+    /// Returns a `Config` with the gains derived from the ultimate gain and period with the specified rule and controller structure.
+    pub fn config(&self, rule: UltimateRule, structure: Structure) -> Config<F> {
+        self.gain(rule, structure).into()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn model() -> Fopdt<f64> {
+        Fopdt {
+            gain: 2.0,
+            time_constant: 10.0,
+            dead_time: 1.0,
+        }
+    }
+
+    fn assert_gain(gain: Gain<f64>, expected: (f64, f64, f64)) {
+        let close = |a: f64, b: f64| (a - b).abs() <= 1e-9 * b.abs().max(1.0);
+        assert!(
+            close(gain.kp, expected.0) && close(gain.ki, expected.1) && close(gain.kd, expected.2),
+            "gain: {:?}, expected: {:?}",
+            gain,
+            expected
+        );
+    }
+
+    #[test]
+    fn test_ziegler_nichols_step() {
+        let model = model();
+        assert_gain(
+            model.gain(ModelRule::ZieglerNichols, Structure::P),
+            (5.0, 0.0, 0.0),
+        );
+        assert_gain(
+            model.gain(ModelRule::ZieglerNichols, Structure::Pi),
+            (4.5, 4.5 * 0.3, 0.0),
+        );
+        assert_gain(
+            model.gain(ModelRule::ZieglerNichols, Structure::Pid),
+            (6.0, 3.0, 3.0),
+        );
+    }
+
+    #[test]
+    fn test_cohen_coon() {
+        let model = model();
+        assert_gain(
+            model.gain(ModelRule::CohenCoon, Structure::P),
+            (5.0 * (1.0 + 0.1 / 3.0), 0.0, 0.0),
+        );
+        let kp = 5.0 * (0.9 + 0.1 / 12.0);
+        assert_gain(
+            model.gain(ModelRule::CohenCoon, Structure::Pi),
+            (kp, kp / (30.3 / 11.0), 0.0),
+        );
+        let kp = 5.0 * (4.0 / 3.0 + 0.1 / 4.0);
+        assert_gain(
+            model.gain(ModelRule::CohenCoon, Structure::Pid),
+            (kp, kp / (32.6 / 13.8), kp * 4.0 / 11.2),
+        );
+    }
+
+    #[test]
+    fn test_simc() {
+        let model = model();
+        assert_gain(
+            model.gain(ModelRule::Simc(1.0), Structure::Pi),
+            (2.5, 2.5 / 8.0, 0.0),
+        );
+        // A fast closed loop is limited by the time constant.
+        assert_gain(
+            model.gain(ModelRule::Simc(4.0), Structure::Pi),
+            (1.0, 0.1, 0.0),
+        );
+        // Series PID with kp = 31 / 12, Ti = 8, Td = 1 / 3.
+        let kp = 31.0 / 12.0 * 25.0 / 24.0;
+        let gain = model.gain(ModelRule::Simc(1.0), Structure::Pid);
+        assert_gain(gain, (kp, kp / (25.0 / 3.0), kp * 8.0 / 25.0));
+    }
+
+    #[test]
+    fn test_lambda() {
+        let model = model();
+        assert_gain(
+            model.gain(ModelRule::Lambda(4.0), Structure::P),
+            (1.0, 0.0, 0.0),
+        );
+        assert_gain(
+            model.gain(ModelRule::Lambda(4.0), Structure::Pi),
+            (1.0, 0.1, 0.0),
+        );
+        assert_gain(
+            model.gain(ModelRule::Lambda(4.5), Structure::Pid),
+            (1.05, 0.1, 1.05 * 10.0 / 21.0),
+        );
+    }
+
+    #[test]
+    fn test_amigo() {
+        let model = model();
+        let kp = 0.075 + (0.35 - 10.0 / 121.0) * 5.0;
+        let ti = 0.35 + 1300.0 / 227.0;
+        assert_gain(
+            model.gain(ModelRule::Amigo, Structure::Pi),
+            (kp, kp / ti, 0.0),
+        );
+        assert_gain(model.gain(ModelRule::Amigo, Structure::P), (kp, 0.0, 0.0));
+        assert_gain(
+            model.gain(ModelRule::Amigo, Structure::Pid),
+            (2.35, 2.35 / 4.2, 2.35 * 5.0 / 10.3),
+        );
+    }
+
+    #[test]
+    fn test_ultimate_rules() {
+        let ultimate = Ultimate {
+            gain: 10.0,
+            period: 2.0,
+        };
+
+        let gain = ultimate.gain(UltimateRule::ZieglerNichols, Structure::Pid);
+        assert_eq!((gain.kp, gain.ki, gain.kd), (6.0, 6.0, 1.5));
+        assert_gain(
+            ultimate.gain(UltimateRule::ZieglerNichols, Structure::Pi),
+            (4.5, 2.7, 0.0),
+        );
+        assert_gain(
+            ultimate.gain(UltimateRule::ZieglerNichols, Structure::P),
+            (5.0, 0.0, 0.0),
+        );
+        assert_gain(
+            ultimate.gain(UltimateRule::TyreusLuyben, Structure::Pid),
+            (10.0 / 2.2, 10.0 / 2.2 / 4.4, 10.0 / 2.2 * 2.0 / 6.3),
+        );
+        // Rules that only define PID gains drop the actions that are not used.
+        assert_gain(
+            ultimate.gain(UltimateRule::NoOvershoot, Structure::Pi),
+            (2.0, 2.0, 0.0),
+        );
+        let config: Config<f32> = Ultimate {
+            gain: 10.0,
+            period: 2.0,
+        }
+        .config(UltimateRule::default(), Structure::default());
+        assert_eq!(config.gain.kp, 6.0);
+    }
+}
@@ -0,0 +1,233 @@
+//! The `two_dof_pid` module provides a two-degree-of-freedom PID controller with setpoint weights.
+//!
+//! `TwoDofPid` is a structure that implements the [`PidController`] trait, which provides methods for creating a new controller and updating the controller.
+//! The proportional action acts on `b * set_point - actual` and the derivative action on `c * set_point - actual`,
+//! where `b` and `c` are the [setpoint weights](crate::config::SetpointWeight) of the configuration.
+//! [`Pid`](crate::Pid) (`b = 1, c = 1`), [`PiD`](crate::PiD) (`b = 1, c = 0`) and [`Ipd`](crate::Ipd) (`b = 0, c = 0`) are special cases.
+//!
+//! # Examples
+//!
+//! ```rust
+//! use advanced_pid::{prelude::*, PidConfig, TwoDofPid};
+//!
+//! let config = PidConfig::new(1.0, 0.3, 0.1).with_setpoint_weights(0.5, 0.0);
+//! let mut pid = TwoDofPid::new(config);
+//!
+//! let target = 1.0;
+//! let actual = 0.0;
+//! let dt = 1.0;
+//!
+//! println!("{}", pid.update(target, actual, dt));
+//! ```
+use super::Configurable;
+use super::Float;
+use super::Integrator;
+use super::ManualMode;
+use super::Mode;
+use super::PidConfig;
+use super::PidController;
+use super::Terms;
+
+/// `TwoDofPid` is a structure that implements the [`PidController`] trait.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(
+    feature = "serde",
+    serde(bound(
+        serialize = "F: Float + serde::Serialize",
+        deserialize = "F: Float + serde::Deserialize<'de>"
+    ))
+)]
+pub struct TwoDofPid<F = f32> {
+    config: PidConfig<F>,
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_float::nan"))]
+    i_term: F,
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_float::nan"))]
+    pre_i_error: F,
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_float::nan"))]
+    pre_d_error: F,
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_float::nan"))]
+    d_term_lpf: F,
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_float::nan"))]
+    pre_output: F,
+    mode: Mode<F>,
+    #[cfg_attr(feature = "serde", serde(skip))]
+    terms: Option<Terms<F>>,
+}
+
+impl<F: Float> Default for TwoDofPid<F> {
+    /// Creates a new `TwoDofPid` with the default configuration.
+    fn default() -> Self {
+        Self::new(PidConfig::default())
+    }
+}
+
+crate::state::impl_positional_snapshot!(TwoDofPid, pre_d_error);
+
+impl<F: Float> PidController<F> for TwoDofPid<F> {
+    /// Creates a new `TwoDofPid` with the specified configuration.
+    fn new(config: PidConfig<F>) -> Self {
+        Self {
+            config,
+            i_term: F::ZERO,
+            pre_i_error: F::NAN,
+            pre_d_error: F::NAN,
+            d_term_lpf: F::ZERO,
+            pre_output: F::ZERO,
+            mode: Mode::Automatic,
+            terms: None,
+        }
+    }
+
+    /// Updates the `TwoDofPid` controller with the specified set point, actual value, and time delta.
+    /// Returns the controller output.
+    fn update(&mut self, set_point: F, actual: F, dt: F) -> F {
+        self.update_with_feedforward(set_point, actual, F::ZERO, dt)
+    }
+
+    /// Updates the `TwoDofPid` controller with the specified set point, actual value, feedforward value, and time delta.
+    /// Returns the controller output.
+    fn update_with_feedforward(&mut self, set_point: F, actual: F, feedforward: F, dt: F) -> F {
+        let (actual, error) = self.config.deadband(set_point, actual);
+        let p_error = self.config.weight.b * set_point - actual;
+        let d_error = self.config.weight.c * set_point - actual;
+        let d_term = if self.pre_d_error.is_nan() {
+            F::ZERO
+        } else {
+            (d_error - self.pre_d_error) / dt
+        };
+        let d_term = self
+            .config
+            .filter_derivative(&mut self.d_term_lpf, d_term, dt);
+        let p_term = self.config.gain.kp * p_error;
+        let d_term = self.config.gain.kd * d_term;
+        let others = p_term + d_term + feedforward;
+        let limits = self.config.output_limits(self.pre_output, dt);
+        let (output, unclamped) = match self.mode {
+            Mode::Automatic => self.config.integrate(
+                &mut self.i_term,
+                self.config.gain.ki * self.config.integral_error(error, self.pre_i_error) * dt,
+                others,
+                dt,
+                limits,
+            ),
+            Mode::Manual(output) => self.config.track(&mut self.i_term, output, others, limits),
+        };
+        self.pre_output = output;
+        self.pre_i_error = error;
+        self.pre_d_error = d_error;
+        let terms = Terms {
+            p: p_term,
+            i: self.i_term,
+            d: d_term,
+            feedforward,
+            unclamped,
+            output: self.config.output_deadband.apply(output),
+            saturated: output <= limits.0 || output >= limits.1,
+        };
+        self.terms = Some(terms);
+        terms.output
+    }
+
+    fn last_terms(&self) -> Option<Terms<F>> {
+        self.terms
+    }
+}
+
+impl<F: Float> Configurable<F> for TwoDofPid<F> {
+    fn config(&self) -> &PidConfig<F> {
+        &self.config
+    }
+
+    fn set_config(&mut self, config: PidConfig<F>) {
+        self.i_term = self.i_term.clamp(config.i_min, config.i_max);
+        self.config = config;
+    }
+}
+
+impl<F: Float> ManualMode<F> for TwoDofPid<F> {
+    fn set_manual(&mut self, output: F) {
+        self.mode = Mode::Manual(output);
+    }
+
+    fn set_auto(&mut self) {
+        self.mode = Mode::Automatic;
+    }
+
+    fn mode(&self) -> Mode<F> {
+        self.mode
+    }
+}
+
+impl<F: Float> Integrator<F> for TwoDofPid<F> {
+    fn integral(&self) -> F {
+        self.i_term
+    }
+
+    fn set_integral(&mut self, integral: F) {
+        self.i_term = integral.clamp(self.config.i_min, self.config.i_max);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Feeds the same inputs to `TwoDofPid` and `reference`, and checks that the outputs match.
+    fn assert_reproduces<C: PidController>(b: f32, c: f32, mut reference: C) {
+        let config = PidConfig::new(1.2, 0.7, 0.3)
+            .with_limits(-2.0, 2.0)
+            .with_setpoint_weights(b, c);
+        let mut pid = TwoDofPid::new(config);
+        let inputs = [
+            (0.0, 0.0),
+            (1.0, 0.0),
+            (1.0, 0.4),
+            (1.5, 0.9),
+            (1.5, 1.6),
+            (0.5, 1.2),
+        ];
+        for (set_point, actual) in inputs {
+            let expected = reference.update(set_point, actual, 0.1);
+            let output = pid.update(set_point, actual, 0.1);
+            assert!(
+                (output - expected).abs() < 1e-5,
+                "output: {} must be equal to {}",
+                output,
+                expected
+            );
+        }
+    }
+
+    fn reference_config() -> PidConfig {
+        PidConfig::new(1.2, 0.7, 0.3).with_limits(-2.0, 2.0)
+    }
+
+    #[test]
+    fn test_two_dof_pid_reproduces_pid() {
+        assert_reproduces(1.0, 1.0, crate::Pid::new(reference_config()));
+    }
+
+    #[test]
+    fn test_two_dof_pid_reproduces_pi_d() {
+        assert_reproduces(1.0, 0.0, crate::PiD::new(reference_config()));
+    }
+
+    #[test]
+    fn test_two_dof_pid_reproduces_i_pd() {
+        assert_reproduces(0.0, 0.0, crate::Ipd::new(reference_config()));
+    }
+
+    #[test]
+    fn test_two_dof_pid_weights() {
+        let config = PidConfig::new(1.0, 0.0, 1.0).with_setpoint_weights(0.5, 0.0);
+        let mut pid = TwoDofPid::new(config);
+
+        let output = pid.update(0.0, 0.0, 1.0);
+        assert_eq!(output, 0.0);
+        let output = pid.update(1.0, 0.0, 1.0);
+        assert_eq!(output, 0.5);
+        let output = pid.update(1.0, 0.5, 1.0);
+        assert_eq!(output, -0.5);
+    }
+}
@@ -16,59 +16,197 @@
 //!
 //! println!("{}", pid.update(target, actual, dt));
 //! ```
-use super::FloatType;
+use super::state::VelocityState;
+use super::Configurable;
+use super::Float;
+use super::Integrator;
+use super::ManualMode;
+use super::Mode;
 use super::PidConfig;
 use super::PidController;
+use super::Snapshot;
+use super::Terms;
 
 /// `VelPid` is a structure that implements the [`PidController`] trait.
 #[derive(Debug, Clone)]
-pub struct VelPid {
-    config: PidConfig,
-    output: FloatType,
-    pre_error: FloatType,
-    pre_p_term: FloatType,
-    d_term_lpf: FloatType,
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(
+    feature = "serde",
+    serde(bound(
+        serialize = "F: Float + serde::Serialize",
+        deserialize = "F: Float + serde::Deserialize<'de>"
+    ))
+)]
+pub struct VelPid<F = f32> {
+    config: PidConfig<F>,
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_float::nan"))]
+    output: F,
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_float::nan"))]
+    pre_error: F,
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_float::nan"))]
+    pre_i_error: F,
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_float::nan"))]
+    pre_p_term: F,
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_float::nan"))]
+    d_term_lpf: F,
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_float::nan"))]
+    pre_feedforward: F,
+    mode: Mode<F>,
+    #[cfg_attr(feature = "serde", serde(skip))]
+    terms: Option<Terms<F>>,
 }
 
-impl Default for VelPid {
+impl<F: Float> Default for VelPid<F> {
     /// Creates a new `VelPid` with the default configuration.
     fn default() -> Self {
         Self::new(PidConfig::default())
     }
 }
 
-impl PidController for VelPid {
+impl<F: Float> Snapshot<F> for VelPid<F> {
+    type State = VelocityState<F>;
+
+    fn snapshot(&self) -> VelocityState<F> {
+        VelocityState {
+            output: self.output,
+            pre_error: self.pre_error,
+            pre_integral_error: self.pre_i_error,
+            pre_error_rate: self.pre_p_term,
+            derivative_filter: self.d_term_lpf,
+            pre_feedforward: self.pre_feedforward,
+            mode: self.mode,
+        }
+    }
+
+    fn from_snapshot(config: PidConfig<F>, state: VelocityState<F>) -> Self {
+        Self {
+            config,
+            output: state.output,
+            pre_error: state.pre_error,
+            pre_i_error: state.pre_integral_error,
+            pre_p_term: state.pre_error_rate,
+            d_term_lpf: state.derivative_filter,
+            pre_feedforward: state.pre_feedforward,
+            mode: state.mode,
+            terms: None,
+        }
+    }
+}
+
+impl<F: Float> PidController<F> for VelPid<F> {
     /// Creates a new `VelPid` with the specified configuration.
-    fn new(config: PidConfig) -> Self {
+    fn new(config: PidConfig<F>) -> Self {
         Self {
             config,
-            output: 0.0,
-            pre_error: 0.0,
-            pre_p_term: FloatType::NAN,
-            d_term_lpf: 0.0,
+            output: F::ZERO,
+            pre_error: F::ZERO,
+            pre_i_error: F::NAN,
+            pre_p_term: F::NAN,
+            d_term_lpf: F::ZERO,
+            pre_feedforward: F::ZERO,
+            mode: Mode::Automatic,
+            terms: None,
         }
     }
 
     /// Updates the `VelPid` controller with the specified set point, actual value, and time delta.
     /// Returns the controller output.
-    fn update(&mut self, set_point: FloatType, actual: FloatType, dt: FloatType) -> FloatType {
-        debug_assert!(dt > 0.0, "dt must be positive");
+    fn update(&mut self, set_point: F, actual: F, dt: F) -> F {
+        self.update_with_feedforward(set_point, actual, F::ZERO, dt)
+    }
+
+    /// Updates the `VelPid` controller with the specified set point, actual value, feedforward value, and time delta.
+    /// Returns the controller output.
+    fn update_with_feedforward(&mut self, set_point: F, actual: F, feedforward: F, dt: F) -> F {
+        debug_assert!(dt > F::ZERO, "dt must be positive");
+        let (actual, i_error) = self.config.deadband(set_point, actual);
         let error = set_point - actual;
         let p_term = (error - self.pre_error) / dt;
         let d_term = if self.pre_p_term.is_nan() {
-            0.0
+            F::ZERO
         } else {
             (p_term - self.pre_p_term) / dt
         };
-        self.d_term_lpf += (d_term - self.d_term_lpf) / 8.0;
+        let d_term = self
+            .config
+            .filter_derivative(&mut self.d_term_lpf, d_term, dt);
+        let integrated = self.config.integral_error(i_error, self.pre_i_error);
         let du = self.config.gain.kp * p_term
-            + self.config.gain.ki * error
-            + self.config.gain.kd * self.d_term_lpf;
+            + self.config.gain.ki * integrated
+            + self.config.gain.kd * d_term;
         self.pre_error = error;
+        self.pre_i_error = i_error;
         self.pre_p_term = p_term;
-        self.output = (self.output + du * dt).clamp(self.config.min, self.config.max);
+        let (min, max) = self.config.output_limits(self.output, dt);
+        let unclamped = match self.mode {
+            // The feedforward value is absolute, so only its change is added.
+            Mode::Automatic => self.output + du * dt + (feedforward - self.pre_feedforward),
+            // Following the manual output makes the next increment start from it.
+            Mode::Manual(output) => output,
+        };
+        // The output is the integrator, so the integrator limits apply to it as well.
+        self.output = unclamped
+            .clamp(self.config.i_min, self.config.i_max)
+            .clamp(min, max);
+        let terms = Terms {
+            p: self.config.gain.kp * p_term * dt,
+            i: self.config.gain.ki * integrated * dt,
+            d: self.config.gain.kd * d_term * dt,
+            feedforward: feedforward - self.pre_feedforward,
+            unclamped,
+            output: self.config.output_deadband.apply(self.output),
+            saturated: self.output <= min
+                || self.output >= max
+                || self.output <= self.config.i_min
+                || self.output >= self.config.i_max,
+        };
+        self.pre_feedforward = feedforward;
+        self.terms = Some(terms);
+        terms.output
+    }
+
+    fn last_terms(&self) -> Option<Terms<F>> {
+        self.terms
+    }
+}
+
+impl<F: Float> Configurable<F> for VelPid<F> {
+    fn config(&self) -> &PidConfig<F> {
+        &self.config
+    }
+
+    fn set_config(&mut self, config: PidConfig<F>) {
+        self.output = self.output.clamp(config.i_min, config.i_max);
+        self.config = config;
+    }
+}
+
+impl<F: Float> ManualMode<F> for VelPid<F> {
+    fn set_manual(&mut self, output: F) {
+        self.mode = Mode::Manual(output);
+    }
+
+    fn set_auto(&mut self) {
+        self.mode = Mode::Automatic;
+    }
+
+    fn mode(&self) -> Mode<F> {
+        self.mode
+    }
+}
+
+impl<F: Float> Integrator<F> for VelPid<F> {
+    /// Returns the output, which is the integrator of the velocity form.
+    fn integral(&self) -> F {
         self.output
     }
+
+    /// Sets the output from which the next increment starts, clamped to the integrator and output limits.
+    fn set_integral(&mut self, integral: F) {
+        self.output = integral
+            .clamp(self.config.i_min, self.config.i_max)
+            .clamp(self.config.min, self.config.max);
+    }
 }
 
 #[cfg(test)]
@@ -130,4 +268,196 @@ mod tests {
         let output = pid.update(-1.0, 0.0, 1.0);
         assert_eq!(output, -0.5);
     }
+
+    #[test]
+    fn test_vel_pid_derivative_filter() {
+        let config = PidConfig::new(0.0, 0.0, 1.0);
+        let mut raw = VelPid::new(config.clone());
+        let config =
+            config.with_derivative_filter(crate::config::DerivativeFilter::TimeConstant(1.0));
+        let mut filtered = VelPid::new(config);
+
+        raw.update(1.0, 0.0, 1.0);
+        filtered.update(1.0, 0.0, 1.0);
+        let raw_output = raw.update(1.0, 5.0, 1.0);
+        let filtered_output = filtered.update(1.0, 5.0, 1.0);
+        assert_eq!(raw_output, -6.0);
+        assert_eq!(filtered_output, -3.0);
+    }
+
+    #[test]
+    fn test_vel_pid_legacy_derivative_filter() {
+        // The update of `VelPid` before version 0.3.0, which always smoothed the derivative by 1/8 per update.
+        let (kp, ki, kd) = (1.0, 0.5, 0.2);
+        let (mut output, mut pre_error, mut pre_p_term, mut lpf) = (0.0, 0.0, f64::NAN, 0.0);
+        let mut legacy = |error: f64, dt: f64| {
+            let p_term = (error - pre_error) / dt;
+            let d_term = if pre_p_term.is_nan() {
+                0.0
+            } else {
+                (p_term - pre_p_term) / dt
+            };
+            lpf += (d_term - lpf) / 8.0;
+            pre_error = error;
+            pre_p_term = p_term;
+            output = (output + (kp * p_term + ki * error + kd * lpf) * dt).clamp(-2.0, 2.0);
+            output
+        };
+
+        let config = PidConfig::new(kp, ki, kd)
+            .with_limits(-2.0, 2.0)
+            .with_derivative_filter(crate::config::DerivativeFilter::vel_pid_legacy());
+        let mut pid: VelPid<f64> = VelPid::new(config);
+        for (k, actual) in [0.0, 0.1, 0.4, 0.9, 1.3, 1.2, 1.0, 0.95]
+            .into_iter()
+            .enumerate()
+        {
+            let dt = if k < 4 { 0.1 } else { 0.05 };
+            let expected = legacy(1.0 - actual, dt);
+            let output = pid.update(1.0, actual, dt);
+            assert!(
+                (output - expected).abs() < 1e-12,
+                "at {}: {} != {}",
+                k,
+                output,
+                expected
+            );
+        }
+    }
+
+    #[test]
+    fn test_vel_pid_integrator() {
+        let mut pid = VelPid::new(PidConfig::new(0.0, 1.0, 0.0).with_limits(-1.0, 1.0));
+
+        // The output is the integrator.
+        pid.set_integral(0.5);
+        assert_eq!(pid.update(1.0, 0.5, 0.5), 0.75);
+        assert_eq!(pid.integral(), 0.75);
+        pid.set_integral(2.0);
+        assert_eq!(pid.integral(), 1.0);
+        // The integrator limits are independent of the output limits.
+        pid.set_integral_limits(-0.5, 0.5).unwrap();
+        assert_eq!(pid.integral_limits(), (-0.5, 0.5));
+        assert_eq!(pid.config().max, 1.0);
+        assert_eq!(pid.integral(), 0.5);
+        assert_eq!(pid.update(1.0, 0.0, 0.5), 0.5);
+        assert_eq!(
+            pid.set_integral_limits(f32::NAN, 0.5),
+            Err(crate::config::ConfigError::NanLimit)
+        );
+        pid.reset_integral();
+        assert_eq!(pid.update(1.0, 1.0, 0.5), 0.0);
+    }
+
+    #[test]
+    fn test_vel_pid_terms() {
+        let mut pid = VelPid::new(PidConfig::new(1.0, 0.5, 0.0).with_limits(-1.0, 1.25));
+        assert_eq!(pid.last_terms(), None);
+
+        // The terms are the increments of the output.
+        let terms = pid.update_detailed(1.0, 0.0, 0.25, 0.5).unwrap();
+        assert_eq!((terms.p, terms.i, terms.feedforward), (1.0, 0.25, 0.25));
+        assert_eq!((terms.unclamped, terms.output), (1.5, 1.25));
+        assert!(terms.saturated);
+
+        let terms = pid.update_detailed(1.0, 0.5, 0.25, 0.5).unwrap();
+        assert_eq!((terms.p, terms.i, terms.feedforward), (-0.5, 0.125, 0.0));
+        assert_eq!((terms.unclamped, terms.output), (0.875, 0.875));
+        assert!(!terms.saturated);
+    }
+
+    #[test]
+    fn test_vel_pid_terms_integral_limits() {
+        let config = PidConfig::new(0.0, 1.0, 0.0).with_integral_limits(-0.5, 0.5);
+        let mut pid = VelPid::new(config);
+
+        // The output pinned at the integrator limit is saturated, as it is at the output limits.
+        let output = pid.update(1.0, 0.0, 1.0);
+        assert_eq!(output, 0.5);
+        assert!(pid.last_terms().unwrap().saturated);
+        assert_eq!(crate::cascade::controller_saturation(&pid, output), 1.0);
+        let output = pid.update(-1.0, 0.0, 0.5);
+        assert_eq!(output, 0.0);
+        assert!(!pid.last_terms().unwrap().saturated);
+    }
+
+    #[test]
+    fn test_vel_pid_discretization_ramp() {
+        use crate::config::Discretization;
+        // The integral of the ramp error `e(t) = t` is `t^2 / 2`.
+        let dt = 0.25;
+        for (discretization, offset) in [
+            (Discretization::ForwardEuler, -0.5),
+            (Discretization::BackwardEuler, 0.5),
+            (Discretization::Tustin, 0.0),
+        ] {
+            let config = PidConfig::new(0.0, 1.0, 0.0).with_discretization(discretization);
+            let mut pid: VelPid<f64> = VelPid::new(config);
+            let mut output = 0.0;
+            for k in 0..=8 {
+                output = pid.update(k as f64 * dt, 0.0, dt);
+            }
+            assert_eq!(output, 2.0 + offset * 2.0 * dt, "{:?}", discretization);
+        }
+    }
+
+    #[test]
+    fn test_vel_pid_bumpless_transfer() {
+        let mut pid = VelPid::new(PidConfig::new(0.0, 1.0, 0.0));
+
+        pid.update(1.0, 0.0, 1.0);
+        pid.set_manual(-0.5);
+        assert_eq!(pid.update(1.0, 0.0, 1.0), -0.5);
+        pid.set_auto();
+        assert_eq!(pid.update(1.0, 0.0, 1.0), 0.5);
+    }
+
+    #[test]
+    fn test_vel_pid_set_config() {
+        let mut pid = VelPid::new(PidConfig::new(0.0, 1.0, 0.0));
+        pid.update(1.0, 0.0, 1.0);
+        pid.update(1.0, 0.0, 1.0);
+
+        pid.set_gain(crate::PidGain {
+            kp: 0.0,
+            ki: 0.5,
+            kd: 0.0,
+        });
+        assert_eq!(pid.update(1.0, 0.0, 1.0), 2.5);
+        pid.reset_config(PidConfig::new(0.0, 0.5, 0.0));
+        assert_eq!(pid.update(1.0, 0.0, 1.0), 0.5);
+    }
+
+    #[test]
+    fn test_vel_pid_rate_limits() {
+        let config = PidConfig::new(1.0, 0.0, 0.0).with_rate_limits(2.0, 4.0);
+        let mut pid = VelPid::new(config);
+
+        assert_eq!(pid.update(1.0, 0.0, 0.25), 0.5);
+        assert_eq!(pid.update(1.0, 0.0, 0.25), 0.5);
+        assert_eq!(pid.update(-1.0, 0.0, 0.25), -0.5);
+    }
+
+    #[test]
+    fn test_vel_pid_deadbands() {
+        let config = PidConfig::new(0.0, 1.0, 0.0)
+            .with_error_deadband(0.25, true)
+            .with_output_deadband(crate::config::OutputDeadband::Zero(1.0));
+        let mut pid = VelPid::new(config);
+
+        assert_eq!(pid.update(1.0, 0.5, 1.0), 0.0);
+        assert_eq!(pid.update(1.0, 0.875, 1.0), 0.0);
+        assert_eq!(pid.update(1.0, 0.5, 1.0), 1.0);
+    }
+
+    #[test]
+    fn test_vel_pid_feedforward() {
+        let config = PidConfig::new(0.0, 1.0, 0.0).with_limits(-2.0, 2.0);
+        let mut pid = VelPid::new(config);
+
+        assert_eq!(pid.update_with_feedforward(1.0, 0.0, 0.5, 1.0), 1.5);
+        assert_eq!(pid.update_with_feedforward(1.0, 0.0, 0.5, 1.0), 2.0);
+        assert_eq!(pid.update_with_feedforward(0.0, 0.0, -0.5, 1.0), 1.0);
+        assert_eq!(pid.update(0.0, 0.0, 1.0), 1.5);
+    }
 }